@@ -0,0 +1,346 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+// Frame rate the demos are capped to unless another one is configured
+pub const TARGET_FPS: u32 = 60;
+
+// Largest factor of the scaled clock, far faster time would overflow a Duration
+const MAX_TIME_SCALE: f32 = 1000.0;
+
+// Upper bound for a single frame's real delta time. Without it a long stall (e.g.
+// a slow terminal) would make the fixed timestep run hundreds of catch-up steps.
+const MAX_DELTA: f32 = 0.25;
+
+// Provides the time a clock is driven by
+pub trait TimeSource {
+    // Time passed since the source was created
+    fn now(&mut self) -> Duration;
+
+    // Longest delta a fixed timestep catches up on at once, None if every delta counts
+    fn max_delta(&self) -> Option<f32> {
+        None
+    }
+}
+
+// Wall-clock time
+pub struct RealTime {
+    start: Instant,
+}
+
+impl RealTime {
+    pub fn new() -> RealTime {
+        RealTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for RealTime {
+    fn default() -> Self {
+        RealTime::new()
+    }
+}
+
+impl TimeSource for RealTime {
+    fn now(&mut self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn max_delta(&self) -> Option<f32> {
+        Some(MAX_DELTA)
+    }
+}
+
+/*
+Simulated time that advances by exactly one step every time it is read. As
+every frame then sees the same delta, runs are reproducible regardless of
+machine speed, which is what recordings, snapshots and benchmarks need.
+*/
+pub struct SimulatedTime {
+    step: Duration,
+    now: Duration,
+}
+
+impl SimulatedTime {
+    pub fn new(step: Duration) -> SimulatedTime {
+        SimulatedTime {
+            step,
+            now: Duration::ZERO,
+        }
+    }
+}
+
+impl TimeSource for SimulatedTime {
+    fn now(&mut self) -> Duration {
+        self.now += self.step;
+        self.now
+    }
+}
+
+// Speeds up or slows down another time source by a constant factor
+pub struct ScaledTime {
+    source: Box<dyn TimeSource>,
+    scale: f32,
+}
+
+impl ScaledTime {
+    pub fn new(source: Box<dyn TimeSource>, scale: f32) -> ScaledTime {
+        ScaledTime { source, scale }
+    }
+}
+
+impl TimeSource for ScaledTime {
+    fn now(&mut self) -> Duration {
+        self.source.now().mul_f32(self.scale)
+    }
+
+    // A stall of the scaled source is just as long in scaled time
+    fn max_delta(&self) -> Option<f32> {
+        self.source
+            .max_delta()
+            .map(|max_delta| max_delta * self.scale)
+    }
+}
+
+// The kinds of clocks that can be selected on the command line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockMode {
+    Real,
+    // Simulated time advancing 1 / fps seconds per frame
+    Fixed(u32),
+    // Real time multiplied by a factor
+    Scaled(f32),
+}
+
+impl ClockMode {
+    pub fn create_clock(&self) -> Clock {
+        match *self {
+            ClockMode::Real => Clock::new(Box::new(RealTime::new())),
+            ClockMode::Fixed(fps) => Clock::new(Box::new(SimulatedTime::new(
+                Duration::from_secs_f32(1.0 / fps.max(1) as f32),
+            ))),
+            ClockMode::Scaled(scale) => {
+                Clock::new(Box::new(ScaledTime::new(Box::new(RealTime::new()), scale)))
+            }
+        }
+    }
+}
+
+// Parses "real", "fixed", "fixed:<fps>" and "scaled:<factor>"
+impl FromStr for ClockMode {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (mode, argument) = match spec.split_once(':') {
+            Some((mode, argument)) => (mode, Some(argument)),
+            None => (spec, None),
+        };
+
+        match (mode, argument) {
+            ("real", None) => Ok(ClockMode::Real),
+            ("fixed", None) => Ok(ClockMode::Fixed(TARGET_FPS)),
+            ("fixed", Some(fps)) => match fps.parse::<u32>() {
+                Ok(fps) if fps > 0 => Ok(ClockMode::Fixed(fps)),
+                _ => Err(format!("invalid frame rate for fixed clock: '{fps}'")),
+            },
+            ("scaled", Some(scale)) => match scale.parse::<f32>() {
+                Ok(scale) if scale > 0.0 && scale <= MAX_TIME_SCALE => {
+                    Ok(ClockMode::Scaled(scale))
+                }
+                _ => Err(format!(
+                    "invalid factor for scaled clock: '{scale}', must be above 0 and at most {MAX_TIME_SCALE}"
+                )),
+            },
+            _ => Err(format!("unknown clock '{spec}'")),
+        }
+    }
+}
+
+// Measures the time passed between frames and since the clock was (re)started
+pub struct Clock {
+    source: Box<dyn TimeSource>,
+    last_tick: Duration,
+    elapsed: f32,
+}
+
+impl Clock {
+    pub fn new(mut source: Box<dyn TimeSource>) -> Clock {
+        Clock {
+            last_tick: source.now(),
+            source,
+            elapsed: 0.0,
+        }
+    }
+
+    // Returns the seconds passed since the previous tick
+    pub fn tick(&mut self) -> f32 {
+        let now = self.source.now();
+        let delta = now.saturating_sub(self.last_tick).as_secs_f32();
+
+        self.last_tick = now;
+        self.elapsed += delta;
+
+        delta
+    }
+
+    // Seconds passed since the clock was (re)started, updated on every tick
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    // Longest delta a fixed timestep driven by this clock should catch up on at once
+    pub fn max_delta(&self) -> Option<f32> {
+        self.source.max_delta()
+    }
+
+    // Starts measuring from zero again, called by every effect when it begins
+    pub fn reset(&mut self) {
+        self.last_tick = self.source.now();
+        self.elapsed = 0.0;
+    }
+}
+
+// Sleeps away whatever is left of the current frame so no demo runs faster
+// than the target frame rate. A target of 0 fps disables the limit.
+pub struct FrameLimiter {
+    frame_duration: Option<Duration>,
+    frame_start: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(target_fps: u32) -> FrameLimiter {
+        FrameLimiter {
+            frame_duration: match target_fps {
+                0 => None,
+                fps => Some(Duration::from_secs_f32(1.0 / fps as f32)),
+            },
+            frame_start: Instant::now(),
+        }
+    }
+
+    pub fn wait(&mut self) {
+        if let Some(frame_duration) = self.frame_duration {
+            let frame_time = self.frame_start.elapsed();
+
+            if frame_time < frame_duration {
+                std::thread::sleep(frame_duration - frame_time);
+            }
+        }
+
+        self.frame_start = Instant::now();
+    }
+}
+
+/*
+Collects the variable frame delta times and hands them out again in steps of
+equal length, so simulations advance the same way no matter how fast frames
+are drawn. Deltas longer than max_delta are cut short, pass Clock::max_delta
+so that only stalls of real time are skipped.
+*/
+pub struct FixedTimestep {
+    step: f32,
+    max_delta: Option<f32>,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32, max_delta: Option<f32>) -> FixedTimestep {
+        FixedTimestep {
+            step,
+            max_delta,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.accumulator += match self.max_delta {
+            Some(max_delta) => delta.min(max_delta),
+            None => delta,
+        };
+    }
+
+    // Returns true as long as another full step is pending
+    pub fn next_step(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            return true;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(timestep: &mut FixedTimestep) -> usize {
+        std::iter::from_fn(|| timestep.next_step().then_some(())).count()
+    }
+
+    #[test]
+    fn simulated_time_advances_one_step_per_read() {
+        let mut time = SimulatedTime::new(Duration::from_millis(20));
+        assert_eq!(time.now(), Duration::from_millis(20));
+        assert_eq!(time.now(), Duration::from_millis(40));
+
+        let mut clock = Clock::new(Box::new(SimulatedTime::new(Duration::from_millis(20))));
+        for _ in 0..50 {
+            assert!((clock.tick() - 0.02).abs() < 1e-6);
+        }
+        assert!((clock.elapsed() - 1.0).abs() < 1e-4);
+        assert_eq!(clock.max_delta(), None);
+
+        clock.reset();
+        assert_eq!(clock.elapsed(), 0.0);
+        assert!((clock.tick() - 0.02).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fixed_timestep_carries_the_remainder() {
+        let mut timestep = FixedTimestep::new(0.1, None);
+
+        timestep.advance(0.25);
+        assert_eq!(steps(&mut timestep), 2);
+
+        // The 0.05 left over adds up with the next delta
+        timestep.advance(0.06);
+        assert_eq!(steps(&mut timestep), 1);
+        timestep.advance(0.0);
+        assert_eq!(steps(&mut timestep), 0);
+    }
+
+    #[test]
+    fn only_limited_clocks_skip_long_deltas() {
+        let mut limited = FixedTimestep::new(0.1, Some(MAX_DELTA));
+        limited.advance(10.0);
+        assert_eq!(steps(&mut limited), 2);
+
+        // Simulated time is never cut short, however long its frames are
+        let clock = ClockMode::Fixed(1).create_clock();
+        let mut unlimited = FixedTimestep::new(0.1, clock.max_delta());
+        unlimited.advance(1.05);
+        assert_eq!(steps(&mut unlimited), 10);
+
+        // Scaled real time stalls for as long as its source
+        let scaled = ClockMode::Scaled(4.0).create_clock();
+        assert_eq!(scaled.max_delta(), Some(MAX_DELTA * 4.0));
+        assert_eq!(ClockMode::Real.create_clock().max_delta(), Some(MAX_DELTA));
+    }
+
+    #[test]
+    fn scale_factors_are_bounded() {
+        assert_eq!("scaled:0.5".parse(), Ok(ClockMode::Scaled(0.5)));
+        assert_eq!("scaled:1000".parse(), Ok(ClockMode::Scaled(1000.0)));
+
+        for spec in [
+            "scaled:0",
+            "scaled:-2",
+            "scaled:1e30",
+            "scaled:inf",
+            "scaled:NaN",
+        ] {
+            assert!(spec.parse::<ClockMode>().is_err(), "{spec} was accepted");
+        }
+    }
+}
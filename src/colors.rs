@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use pancurses::chtype;
+
+// The eight standard curses colors, usable as foreground on the default background
+pub const BLACK: i16 = pancurses::COLOR_BLACK;
+pub const RED: i16 = pancurses::COLOR_RED;
+pub const GREEN: i16 = pancurses::COLOR_GREEN;
+pub const YELLOW: i16 = pancurses::COLOR_YELLOW;
+pub const BLUE: i16 = pancurses::COLOR_BLUE;
+pub const MAGENTA: i16 = pancurses::COLOR_MAGENTA;
+pub const CYAN: i16 = pancurses::COLOR_CYAN;
+pub const WHITE: i16 = pancurses::COLOR_WHITE;
+
+const COLOR_COUNT: i16 = 8;
+
+// Stays false on terminals without color support, effects then draw uncolored
+static COLORS_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+// Sets up one color pair per foreground color, pair n + 1 holding color n
+pub fn init() {
+    if !pancurses::has_colors() {
+        return;
+    }
+
+    pancurses::start_color();
+
+    // -1 keeps the terminal's own background if it supports that
+    let background = match pancurses::use_default_colors() {
+        pancurses::OK => -1,
+        _ => BLACK,
+    };
+
+    for color in 0..COLOR_COUNT {
+        pancurses::init_pair(color + 1, color, background);
+    }
+
+    COLORS_AVAILABLE.store(true, Ordering::Relaxed);
+}
+
+// Attribute drawing in the given foreground color
+pub fn fg(color: i16) -> chtype {
+    match COLORS_AVAILABLE.load(Ordering::Relaxed) {
+        true => pancurses::COLOR_PAIR((color + 1) as chtype),
+        false => 0,
+    }
+}
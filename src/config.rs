@@ -0,0 +1,364 @@
+use crate::clock::{ClockMode, TARGET_FPS};
+use crate::cube3d::TitleStyle;
+use crate::error::DemoError;
+use crate::expr::Expr;
+use crate::figlet::{Font, Layout};
+use crate::hershey::StrokeFont;
+use crate::life::{Pattern, Rule};
+use crate::overlay::Overlay;
+use crate::palette::{CycleMode, Palette};
+use crate::primitives::Shape;
+use crate::raycaster::Map;
+use crate::rng::Rng;
+use crate::texture::Texture;
+use crate::voxel::{Colormap, Heightmap};
+
+pub const USAGE: &str = "Usage: rustncurses3ddemo [OPTIONS]
+
+Options:
+    --fps <N>             Frame rate cap, 0 disables the cap (default: 60)
+    --clock <MODE>        Time source driving the effects:
+                            real             wall-clock time (default)
+                            fixed[:<fps>]    simulated time advancing one frame
+                                             per draw, for reproducible runs
+                            scaled:<factor>  wall-clock time sped up or slowed down,
+                                             by at most 1000 times
+    --effects <LIST>      Comma separated effects to show, in order, out of
+                          cube, plasma, fire, starfield, tunnel, rotozoom,
+                          metaballs, rain, life, raycaster, voxel
+                          (default: cube,plasma)
+    --loop                Screensaver mode: repeat the effects endlessly with
+                          randomized parameters until any key is pressed
+    --seed <N>            Seed for randomized parameters (default: current time)
+    --formula <EXPR>      Custom plasma field, e.g. \"sin(x / 8 + t) * 128\". Knows
+                          x, y, t, w, h, pi, e, + - * / % ^ and the functions
+                          sin cos tan asin acos atan sqrt abs floor ceil round
+                          exp ln fract min max pow atan2 hypot. The palette
+                          repeats every 256 units.
+    --palette <NAME>      Plasma, metaballs and life characters: classic (default),
+                          ascii, blocks, braille or custom:<characters>
+    --colors <NAME>       Plasma, metaballs and life color gradient: none (default),
+                          fire, ocean, toxic, rainbow
+    --cycle <MODE>        Palette cycling: linear (default), pingpong, stepped
+    --threads <N>         Threads computing the plasma (default: all cores), small
+                          terminals use fewer
+    --bench               Measure plasma throughput instead of running the demo
+    --fire-intensity <F>  Share of the fire's base seeded with heat, 0 to 1
+                          (default: 0.8)
+    --fire-wind <F>       Sideways drift of the flames, -1 to 1 (default: 0)
+    --star-speed <F>      Starting speed of the starfield, changed with +/- or the
+                          arrow keys while it runs (default: 5)
+    --rain-speed <F>      Speed multiplier of the digital rain (default: 1)
+    --life-rule <RULE>    Cellular automaton rule: B<counts>/S<counts> for Life-like
+                          rules (default: B3/S23) or W<n> for the elementary
+                          one dimensional rule n, e.g. W30 or W110
+    --life-pattern <FILE> RLE pattern the automaton starts with instead of a
+                          random soup, a rule in its header replaces the rule
+    --map <FILE>          Text file with the raycaster's map: spaces are floor,
+                          '@' the start, digits 1-6 colored and any other
+                          character white walls. Walk with WASD or the arrows.
+    --heightmap <FILE>    Grayscale PGM image (P2 or P5) the voxel terrain is
+                          made of instead of a generated landscape
+    --colormap <FILE>     Color PPM image (P3 or P6) of the voxel terrain, every
+                          pixel drawn in the closest terminal color, instead of
+                          colors generated from height and moisture
+    --texture <FILE>      Text file used as texture by the tunnel and the
+                          rotozoomer instead of the built-in XOR pattern
+    --overlay <MOTION>    Text drawn on top of every effect: scroll (a sine
+                          scroller), bounce, center or none (default)
+    --overlay-text <TEXT> Message of the overlay, enables a scroller if no
+                          motion is given
+    --overlay-speed <F>   Speed multiplier of the overlay (default: 1)
+    --overlay-style <LIST> Comma separated attributes and color of the overlay
+                          out of bold (default), dim, blink, reverse, underline,
+                          red, green, yellow, blue, magenta, cyan, white,
+                          or big for a banner in the FIGlet font
+    --font <FILE>         FIGlet font (.flf) of the titles, the outro and big
+                          overlays instead of the built-in one
+    --font-layout <MODE>  How close banner letters are put: full (full width),
+                          fit (touching) or smush (merged by the font's rules),
+                          default: as set in the font
+    --shape <NAME>        Mesh spinning in the cube effect: cube (default), sphere,
+                          icosphere[:<level>] (subdivided 0 to 4 times,
+                          default: 1), torus, cylinder, cone or plane
+    --cube-title <STYLE>  How the cube's title is drawn: banner (default), vector
+                          (Hershey font strokes), mesh (extruded strokes) or
+                          block (solid FIGlet letters), the last two spinning
+                          with the cube
+    --stroke-font <FILE>  Hershey font (.jhf) of vector titles instead of the
+                          built-in one, which has no lower case letters
+    --scene <FILE>        Read options from a file with one \"option = value\"
+                          per line, lines starting with '#' are comments
+    -h, --help            Print this help";
+
+// Settings for a demo run, taken from the command line
+#[derive(Clone)]
+pub struct Config {
+    pub fps: u32,
+    pub clock_mode: ClockMode,
+    pub effects: Vec<String>,
+    pub loop_mode: bool,
+    pub seed: u64,
+    pub threads: usize,
+    pub benchmark: bool,
+
+    // Cube rotation speed multiplier and distance from the camera
+    pub cube_spin: f32,
+    pub cube_distance: f32,
+    pub cube_title: TitleStyle,
+    pub shape: Shape,
+
+    // Speed of the plasma animation and of the palette cycling
+    pub plasma_speed: f32,
+    pub plasma_cycle_speed: f32,
+    pub fire_intensity: f32,
+    pub fire_wind: f32,
+
+    pub star_speed: f32,
+    pub rain_speed: f32,
+
+    // Cellular automaton rule and the pattern it starts from, a random soup if not set
+    pub life_rule: Rule,
+    pub life_pattern: Option<Pattern>,
+
+    // Replaces the built-in raycaster map if set
+    pub map: Option<Map>,
+
+    // Replace the generated voxel terrain heights and colors if set
+    pub heightmap: Option<Heightmap>,
+    pub colormap: Option<Colormap>,
+
+    // Replaces the procedural texture of the texture effects if set
+    pub texture: Option<Texture>,
+
+    // Replaces the built-in plasma field if set
+    pub plasma_formula: Option<Expr>,
+    pub palette: Palette,
+    pub cycle_mode: CycleMode,
+
+    // Text layered over every effect if set
+    pub overlay: Option<Overlay>,
+
+    // Font of banner text, with the layout chosen on the command line if any
+    pub font: Font,
+    pub font_layout: Option<Layout>,
+
+    // Font of vector titles
+    pub stroke_font: StrokeFont,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            fps: TARGET_FPS,
+            clock_mode: ClockMode::Real,
+            effects: crate::DEFAULT_EFFECTS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            loop_mode: false,
+            seed: Rng::time_seed(),
+            threads: std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+            benchmark: false,
+            cube_spin: 1.0,
+            cube_distance: 2.5,
+            cube_title: TitleStyle::Banner,
+            shape: Shape::Cube,
+            plasma_speed: 1.0,
+            plasma_cycle_speed: 100.0,
+            fire_intensity: 0.8,
+            fire_wind: 0.0,
+            star_speed: 5.0,
+            rain_speed: 1.0,
+            life_rule: Rule::default(),
+            life_pattern: None,
+            map: None,
+            heightmap: None,
+            colormap: None,
+            texture: None,
+            plasma_formula: None,
+            palette: Palette::default(),
+            cycle_mode: CycleMode::Linear,
+            overlay: None,
+            font: Font::default(),
+            font_layout: None,
+            stroke_font: StrokeFont::default(),
+        }
+    }
+}
+
+impl Config {
+    // Returns None if the help text was requested
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Option<Config>, DemoError> {
+        let mut config = Config::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--loop" => config.loop_mode = true,
+                "--bench" => config.benchmark = true,
+                _ => match arg.strip_prefix("--") {
+                    Some(key) => {
+                        let value = args.next().ok_or_else(|| {
+                            DemoError::Config(format!("missing value for '{arg}'"))
+                        })?;
+                        config.set(key, &value)?;
+                    }
+                    None => return Err(DemoError::Config(format!("unexpected argument '{arg}'"))),
+                },
+            }
+        }
+
+        Ok(Some(config))
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), DemoError> {
+        match key {
+            "fps" => {
+                self.fps = value
+                    .parse()
+                    .map_err(|_| DemoError::Config(format!("invalid frame rate '{value}'")))?
+            }
+            "clock" => self.clock_mode = value.parse().map_err(DemoError::Config)?,
+            "effects" => {
+                self.effects = value
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect();
+
+                if let Some(unknown) = self
+                    .effects
+                    .iter()
+                    .find(|name| !crate::EFFECTS.iter().any(|(effect, _)| effect == name))
+                {
+                    return Err(DemoError::Config(format!("unknown effect '{unknown}'")));
+                }
+            }
+            "seed" => {
+                self.seed = value
+                    .parse()
+                    .map_err(|_| DemoError::Config(format!("invalid seed '{value}'")))?
+            }
+            "threads" => {
+                self.threads = match value.parse() {
+                    Ok(threads) if threads > 0 => threads,
+                    _ => return Err(DemoError::Config(format!("invalid thread count '{value}'"))),
+                }
+            }
+            "fire-intensity" => self.fire_intensity = parse_ranged(value, 0.0, 1.0)?,
+            "fire-wind" => self.fire_wind = parse_ranged(value, -1.0, 1.0)?,
+            "star-speed" => self.star_speed = parse_ranged(value, 0.0, 40.0)?,
+            "rain-speed" => self.rain_speed = parse_ranged(value, 0.0, 10.0)?,
+            "life-rule" => self.life_rule = value.parse()?,
+            "life-pattern" => {
+                let pattern = Pattern::load(value.as_ref())?;
+                if let Some(rule) = pattern.rule {
+                    self.life_rule = rule;
+                }
+                self.life_pattern = Some(pattern);
+            }
+            "map" => self.map = Some(Map::load(value.as_ref())?),
+            "heightmap" => self.heightmap = Some(Heightmap::load(value.as_ref())?),
+            "colormap" => self.colormap = Some(Colormap::load(value.as_ref())?),
+            "texture" => self.texture = Some(Texture::load(value.as_ref())?),
+            "formula" => self.plasma_formula = Some(Expr::compile(value)?),
+            "palette" => self.palette.set_glyphs(value)?,
+            "colors" => self.palette.set_colors(value)?,
+            "cycle" => self.cycle_mode = value.parse()?,
+            "overlay" => match value {
+                "none" => self.overlay = None,
+                motion => self.overlay_mut().motion = motion.parse()?,
+            },
+            "overlay-text" => self.overlay_mut().text = value.to_string(),
+            "overlay-speed" => self.overlay_mut().speed = parse_ranged(value, -10.0, 10.0)?,
+            "overlay-style" => self.overlay_mut().style = value.parse()?,
+            "font" => {
+                self.font = Font::load(value.as_ref())?;
+                if let Some(layout) = self.font_layout {
+                    self.font.layout = layout;
+                }
+            }
+            "font-layout" => {
+                let layout = value.parse()?;
+                self.font.layout = layout;
+                self.font_layout = Some(layout);
+            }
+            "shape" => self.shape = value.parse()?,
+            "cube-title" => self.cube_title = value.parse()?,
+            "stroke-font" => self.stroke_font = StrokeFont::load(value.as_ref())?,
+            "scene" => self.load_scene(value)?,
+            "loop" => {
+                self.loop_mode = value
+                    .parse()
+                    .map_err(|_| DemoError::Config(format!("invalid loop flag '{value}'")))?
+            }
+            _ => return Err(DemoError::Config(format!("unknown option '--{key}'"))),
+        }
+
+        Ok(())
+    }
+
+    // The overlay options refine the default overlay unless one was set up before
+    fn overlay_mut(&mut self) -> &mut Overlay {
+        self.overlay.get_or_insert_with(Overlay::default)
+    }
+
+    // Scene files take the same options as the command line, as "option = value" lines
+    fn load_scene(&mut self, path: &str) -> Result<(), DemoError> {
+        let source = std::fs::read_to_string(path).map_err(|error| DemoError::Io {
+            path: path.into(),
+            error,
+        })?;
+
+        for (number, line) in source.lines().enumerate() {
+            // Values may contain '#' themselves, so only whole lines are comments
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                DemoError::Config(format!("{path}:{}: expected 'option = value'", number + 1))
+            })?;
+
+            if key.trim() == "scene" {
+                return Err(DemoError::Config(format!(
+                    "{path}:{}: scene files can't include other scenes",
+                    number + 1
+                )));
+            }
+
+            self.set(key.trim(), value.trim())
+                .map_err(|error| DemoError::Config(format!("{path}:{}: {error}", number + 1)))?;
+        }
+
+        Ok(())
+    }
+
+    // A copy with the effect parameters shuffled, used for every cycle of the loop mode
+    pub fn randomized(&self, rng: &mut Rng) -> Config {
+        Config {
+            seed: rng.next_u64(),
+            cube_spin: rng.range(0.5, 2.0),
+            cube_distance: rng.range(2.0, 4.0),
+            plasma_speed: rng.range(0.5, 2.0),
+            plasma_cycle_speed: rng.range(50.0, 200.0),
+            fire_intensity: rng.range(0.5, 1.0),
+            fire_wind: rng.range(-0.5, 0.5),
+            star_speed: rng.range(2.0, 15.0),
+            rain_speed: rng.range(0.5, 2.0),
+            ..self.clone()
+        }
+    }
+}
+
+fn parse_ranged(value: &str, min: f32, max: f32) -> Result<f32, DemoError> {
+    match value.parse::<f32>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(DemoError::Config(format!(
+            "'{value}' is not a number from {min} to {max}"
+        ))),
+    }
+}
@@ -1,21 +1,70 @@
 // Not all matrix transformation functions are used in this demo
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::ops;
+use std::str::FromStr;
+
+use crate::clock::{Clock, FixedTimestep, FrameLimiter};
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::hershey::StrokeFont;
+use crate::input;
+use crate::overlay::{self, Motion, Overlay, Style};
+use crate::terminal;
+use crate::CELL_ASPECT;
 
 const TRIANGLE_VERTEX_COUNT: usize = 3;
 const VERTEX_INDEX_1: usize = 0;
 const VERTEX_INDEX_2: usize = 1;
 const VERTEX_INDEX_3: usize = 2;
 
+// The cube animation advances in fixed steps of 10ms, each turning it by 0.005 rad
+const CUBE_STEP_SECONDS: f32 = 0.01;
+const CUBE_STEP_THETA: f32 = 0.005;
+
+const CUBE_TITLE: &str = "= 3D CUBE =";
+
+// Width of the extruded title in world units, how thick it is and how far below the cube center
+const TITLE_MESH_WIDTH: f32 = 7.0;
+const TITLE_MESH_DEPTH: f32 = 0.3;
+const TITLE_MESH_OFFSET: f32 = 1.3;
+
+// How the cube's title is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TitleStyle {
+    // A bouncing banner in the FIGlet font
+    Banner,
+    // Bouncing vector strokes in the Hershey font
+    Vector,
+    // The Hershey strokes extruded into a mesh, spinning along with the cube
+    Mesh,
+    // The FIGlet banner extruded into solid letters, spinning along with the cube
+    Block,
+}
+
+impl FromStr for TitleStyle {
+    type Err = DemoError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "banner" => Ok(TitleStyle::Banner),
+            "vector" => Ok(TitleStyle::Vector),
+            "mesh" => Ok(TitleStyle::Mesh),
+            "block" => Ok(TitleStyle::Block),
+            _ => Err(DemoError::Config(format!("unknown title style '{name}'"))),
+        }
+    }
+}
+
 #[derive(Clone)]
-struct Vec2 {
-    x: f32,
-    y: f32,
+pub(crate) struct Vec2 {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
 }
 
 impl Vec2 {
-    fn round(&self) -> Vec2 {
+    pub(crate) fn round(&self) -> Vec2 {
         Vec2 {
             x: self.x.round(),
             y: self.y.round(),
@@ -24,14 +73,14 @@ impl Vec2 {
 }
 
 #[derive(Clone)]
-struct Vec3 {
-    x: f32,
-    y: f32,
-    z: f32,
+pub(crate) struct Vec3 {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) z: f32,
 }
 
 impl Vec3 {
-    fn project(&self) -> Vec3 {
+    pub(crate) fn project(&self) -> Vec3 {
         let near: f32 = 0.1;
         let far: f32 = 1000.0;
         let fov: f32 = 90.0;
@@ -80,7 +129,7 @@ impl Vec3 {
         rotate_z_matrix * self
     }
 
-    fn rotate_z(&self, theta: f32) -> Vec3 {
+    pub(crate) fn rotate_z(&self, theta: f32) -> Vec3 {
         let rotate_z_matrix = Matrix4x4 {
             data: [
                 [f32::cos(theta), f32::sin(theta), 0.0, 0.0],
@@ -119,12 +168,12 @@ impl Vec3 {
 }
 
 #[derive(Clone)]
-struct Vertex {
-    position: Vec3,
+pub(crate) struct Vertex {
+    pub(crate) position: Vec3,
 }
 
 impl Vertex {
-    fn scale_into_screen_2d(&self) -> Vec2 {
+    pub(crate) fn scale_into_screen_2d(&self) -> Vec2 {
         unsafe {
             Vec2 {
                 x: (&self.position.x + 1.0) * 0.5 * crate::SCREEN_WIDTH,
@@ -172,152 +221,145 @@ impl ops::Mul<&Vec3> for Matrix4x4 {
 }
 
 #[derive(Clone)]
-struct Triangle {
-    vertices: Vec<Vertex>,
+pub(crate) struct Triangle {
+    pub(crate) vertices: [Vertex; TRIANGLE_VERTEX_COUNT],
+}
+
+impl TryFrom<Vec<Vertex>> for Triangle {
+    type Error = DemoError;
+
+    fn try_from(vertices: Vec<Vertex>) -> Result<Self, Self::Error> {
+        let vertex_count = vertices.len();
+
+        Ok(Triangle {
+            vertices: vertices.try_into().map_err(|_| {
+                DemoError::InvalidMesh(format!(
+                    "triangle needs {TRIANGLE_VERTEX_COUNT} vertices, got {vertex_count}"
+                ))
+            })?,
+        })
+    }
 }
 
 impl Triangle {
     fn project(&self) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.project(),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.project(),
+                }
+            }),
         }
     }
 
     fn rotate_x(&self, theta: f32) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.rotate_x(theta),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.rotate_x(theta),
+                }
+            }),
         }
     }
 
     fn rotate_y(&self, theta: f32) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.rotate_y(theta),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.rotate_y(theta),
+                }
+            }),
         }
     }
 
     fn rotate_z(&self, theta: f32) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.rotate_z(theta),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.rotate_z(theta),
+                }
+            }),
         }
     }
 
     fn translate_x(&self, x: f32) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.translate_x(x),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.translate_x(x),
+                }
+            }),
         }
     }
 
     fn translate_y(&self, y: f32) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.translate_y(y),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.translate_y(y),
+                }
+            }),
         }
     }
 
     fn translate_z(&self, z: f32) -> Triangle {
         Triangle {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|vertex| -> Vertex {
-                    Vertex {
-                        position: vertex.position.translate_z(z),
-                    }
-                })
-                .collect(),
+            vertices: self.vertices.each_ref().map(|vertex| -> Vertex {
+                Vertex {
+                    position: vertex.position.translate_z(z),
+                }
+            }),
         }
     }
 
-    fn draw(&self, window: &pancurses::Window) {
+    fn draw(&self, window: &pancurses::Window) -> Result<(), DemoError> {
         // Scale the vertexs x and y coordinates into the screen dimensions
-        let scaled_vertices: Vec<Vec2> = self
+        let scaled_vertices: [Vec2; TRIANGLE_VERTEX_COUNT] = self
             .vertices
-            .iter()
-            .map(|vertex: &Vertex| -> Vec2 { vertex.scale_into_screen_2d() })
-            .collect();
-
-        match scaled_vertices.len() {
-            TRIANGLE_VERTEX_COUNT => {
-                draw_line(
-                    window,
-                    &scaled_vertices[VERTEX_INDEX_1],
-                    &scaled_vertices[VERTEX_INDEX_2],
-                );
-                draw_line(
-                    window,
-                    &scaled_vertices[VERTEX_INDEX_2],
-                    &scaled_vertices[VERTEX_INDEX_3],
-                );
-                draw_line(
-                    window,
-                    &scaled_vertices[VERTEX_INDEX_1],
-                    &scaled_vertices[VERTEX_INDEX_3],
-                );
-            }
-            _ => {
-                eprintln!(
-                    "ERROR: Triangle is missing {} vertex / vertices!",
-                    TRIANGLE_VERTEX_COUNT - scaled_vertices.len()
-                );
-                std::process::exit(1);
-            }
-        }
+            .each_ref()
+            .map(|vertex: &Vertex| -> Vec2 { vertex.scale_into_screen_2d() });
+
+        draw_line(
+            window,
+            &scaled_vertices[VERTEX_INDEX_1],
+            &scaled_vertices[VERTEX_INDEX_2],
+        )?;
+        draw_line(
+            window,
+            &scaled_vertices[VERTEX_INDEX_2],
+            &scaled_vertices[VERTEX_INDEX_3],
+        )?;
+        draw_line(
+            window,
+            &scaled_vertices[VERTEX_INDEX_1],
+            &scaled_vertices[VERTEX_INDEX_3],
+        )
     }
 }
 
-struct Mesh {
-    triangles: Vec<Triangle>,
+pub(crate) struct Mesh {
+    pub(crate) triangles: Vec<Triangle>,
 }
 
 impl Mesh {
-    fn project(&self) -> Mesh {
+    // Builds a mesh from a flat list of vertices, every three forming a triangle
+    pub(crate) fn from_vertices(vertices: Vec<Vertex>) -> Result<Mesh, DemoError> {
+        if !vertices.len().is_multiple_of(TRIANGLE_VERTEX_COUNT) {
+            return Err(DemoError::InvalidMesh(format!(
+                "vertex count {} is not a multiple of {TRIANGLE_VERTEX_COUNT}",
+                vertices.len()
+            )));
+        }
+
+        Ok(Mesh {
+            triangles: vertices
+                .chunks(TRIANGLE_VERTEX_COUNT)
+                .map(|chunk| Triangle::try_from(chunk.to_vec()))
+                .collect::<Result<Vec<Triangle>, DemoError>>()?,
+        })
+    }
+
+    pub(crate) fn project(&self) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -327,7 +369,7 @@ impl Mesh {
         }
     }
 
-    fn rotate_x(&self, theta: f32) -> Mesh {
+    pub(crate) fn rotate_x(&self, theta: f32) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -337,7 +379,7 @@ impl Mesh {
         }
     }
 
-    fn rotate_y(&self, theta: f32) -> Mesh {
+    pub(crate) fn rotate_y(&self, theta: f32) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -347,7 +389,7 @@ impl Mesh {
         }
     }
 
-    fn rotate_z(&self, theta: f32) -> Mesh {
+    pub(crate) fn rotate_z(&self, theta: f32) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -357,7 +399,7 @@ impl Mesh {
         }
     }
 
-    fn translate_x(&self, x: f32) -> Mesh {
+    pub(crate) fn translate_x(&self, x: f32) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -367,7 +409,7 @@ impl Mesh {
         }
     }
 
-    fn translate_y(&self, y: f32) -> Mesh {
+    pub(crate) fn translate_y(&self, y: f32) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -377,7 +419,7 @@ impl Mesh {
         }
     }
 
-    fn translate_z(&self, z: f32) -> Mesh {
+    pub(crate) fn translate_z(&self, z: f32) -> Mesh {
         Mesh {
             triangles: self
                 .triangles
@@ -387,14 +429,101 @@ impl Mesh {
         }
     }
 
-    fn draw(&self, window: &pancurses::Window) {
+    pub(crate) fn draw(&self, window: &pancurses::Window) -> Result<(), DemoError> {
         self.triangles
             .iter()
-            .for_each(|triangle| triangle.draw(window));
+            .try_for_each(|triangle| triangle.draw(window))
+    }
+}
+
+/*
+A mesh whose triangles share their corners: every distinct vertex is stored
+once and the triangles refer to them by index. Each vertex is transformed once
+per frame however many triangles it belongs to, where the triangle soup of a
+Mesh transforms every corner of every triangle, the cube's 8 corners 36 times.
+*/
+pub(crate) struct IndexedMesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<[usize; TRIANGLE_VERTEX_COUNT]>,
+}
+
+impl IndexedMesh {
+    // Merges the corners of the triangles that are at exactly the same position
+    pub(crate) fn from_mesh(mesh: &Mesh) -> IndexedMesh {
+        let mut vertices = Vec::new();
+        let mut positions: HashMap<[u32; 3], usize> = HashMap::new();
+
+        let indices = mesh
+            .triangles
+            .iter()
+            .map(|triangle| {
+                triangle.vertices.each_ref().map(|vertex| {
+                    // Adding zero turns -0.0 into 0.0, so both have the same bits
+                    let position = &vertex.position;
+                    let key = [position.x, position.y, position.z].map(|c| (c + 0.0).to_bits());
+
+                    *positions.entry(key).or_insert_with(|| {
+                        vertices.push(vertex.clone());
+                        vertices.len() - 1
+                    })
+                })
+            })
+            .collect();
+
+        IndexedMesh { vertices, indices }
+    }
+
+    pub(crate) fn to_mesh(&self) -> Mesh {
+        Mesh {
+            triangles: self
+                .indices
+                .iter()
+                .map(|corners| Triangle {
+                    vertices: corners.map(|index| self.vertices[index].clone()),
+                })
+                .collect(),
+        }
+    }
+
+    /*
+    Runs every vertex through transform, which has to end with the projection,
+    and draws the triangles. The screen positions are kept in screen, so its
+    allocation can be reused from frame to frame.
+    */
+    pub(crate) fn draw(
+        &self,
+        window: &pancurses::Window,
+        transform: impl Fn(&Vec3) -> Vec3,
+        screen: &mut Vec<Vec2>,
+    ) -> Result<(), DemoError> {
+        screen.clear();
+        screen.extend(self.vertices.iter().map(|vertex| {
+            Vertex {
+                position: transform(&vertex.position),
+            }
+            .scale_into_screen_2d()
+        }));
+
+        self.indices.iter().try_for_each(|&[a, b, c]| {
+            draw_line(window, &screen[a], &screen[b])?;
+            draw_line(window, &screen[b], &screen[c])?;
+            draw_line(window, &screen[a], &screen[c])
+        })
     }
 }
 
-fn draw_line(window: &pancurses::Window, vec0: &Vec2, vec1: &Vec2) {
+pub(crate) fn draw_line(
+    window: &pancurses::Window,
+    vec0: &Vec2,
+    vec1: &Vec2,
+) -> Result<(), DemoError> {
+    // Stepping towards an infinite or NaN coordinate would never terminate
+    if !(vec0.x.is_finite() && vec0.y.is_finite() && vec1.x.is_finite() && vec1.y.is_finite()) {
+        return Err(DemoError::Render(
+            "line endpoint is not a finite coordinate".to_string(),
+        ));
+    }
+
     // The vertices are rounded as a simple rasterization method
     let mut vec0_rounded = vec0.round();
     let vec1_rounded = vec1.round();
@@ -437,30 +566,16 @@ fn draw_line(window: &pancurses::Window, vec0: &Vec2, vec1: &Vec2) {
             vec0_rounded.y += sy;
         }
     }
-}
-
-fn draw_text(window: &pancurses::Window, theta: f32) {
-    let y: f32;
-    let x: f32;
-    let text = "= 3D CUBE =";
-
-    unsafe {
-        y = (f32::sin(theta).abs() * crate::SCREEN_HEIGHT).round();
-        x = ((f32::sin(theta * 5.0) * crate::SCREEN_WIDTH / 2.0) + crate::SCREEN_WIDTH / 2.0)
-            .round();
-    }
 
-    window.attron(pancurses::A_BOLD);
-    window.mvaddstr(y as i32, x as i32, text);
-    window.attroff(pancurses::A_BOLD);
+    Ok(())
 }
 
-fn create_cube() -> Mesh {
+pub(crate) fn create_cube() -> Mesh {
     Mesh {
         triangles: Vec::from([
             // SOUTH
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: -1.0,
@@ -485,7 +600,7 @@ fn create_cube() -> Mesh {
                 ],
             },
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: -1.0,
@@ -511,7 +626,7 @@ fn create_cube() -> Mesh {
             },
             // EAST
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: 1.0,
@@ -536,7 +651,7 @@ fn create_cube() -> Mesh {
                 ],
             },
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: 1.0,
@@ -562,7 +677,7 @@ fn create_cube() -> Mesh {
             },
             //NORTH
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: 1.0,
@@ -587,7 +702,7 @@ fn create_cube() -> Mesh {
                 ],
             },
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: 1.0,
@@ -613,7 +728,7 @@ fn create_cube() -> Mesh {
             },
             // WEST
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: -1.0,
@@ -638,7 +753,7 @@ fn create_cube() -> Mesh {
                 ],
             },
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: -1.0,
@@ -664,7 +779,7 @@ fn create_cube() -> Mesh {
             },
             // TOP
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: -1.0,
@@ -689,7 +804,7 @@ fn create_cube() -> Mesh {
                 ],
             },
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: -1.0,
@@ -715,7 +830,7 @@ fn create_cube() -> Mesh {
             },
             // BOTTOM
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: 1.0,
@@ -740,7 +855,7 @@ fn create_cube() -> Mesh {
                 ],
             },
             Triangle {
-                vertices: vec![
+                vertices: [
                     Vertex {
                         position: Vec3 {
                             x: 1.0,
@@ -768,37 +883,187 @@ fn create_cube() -> Mesh {
     }
 }
 
-pub fn run_cube_demo(window: &pancurses::Window) {
-    let cube = create_cube();
+// The title in vector strokes a quarter of the screen high, bouncing like the banner
+fn draw_vector_title(
+    window: &pancurses::Window,
+    font: &StrokeFont,
+    t: f32,
+) -> Result<(), DemoError> {
+    let (screen_width, screen_height) = terminal::screen_size();
+
+    let strokes = font.render(CUBE_TITLE)?;
+    let scale = f32::min(
+        screen_height / 4.0 * CELL_ASPECT / strokes.height.max(1.0),
+        screen_width * 0.9 / strokes.width.max(1.0),
+    );
+    let (width, height) = (strokes.width * scale, strokes.height * scale / CELL_ASPECT);
+
+    let center = Vec2 {
+        x: width / 2.0 + (0.5 + 0.5 * f32::sin(t * 5.0)) * (screen_width - width).max(0.0),
+        y: height / 2.0 + f32::sin(t).abs() * (screen_height - height - 1.0).max(0.0),
+    };
+
+    window.attrset(pancurses::A_BOLD);
+    let result = strokes.draw(window, &center, scale);
+    window.attrset(pancurses::A_NORMAL);
+    result
+}
+
+pub fn run_cube_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    let cube = IndexedMesh::from_mesh(&config.shape.mesh()?);
+    let mut screen = Vec::new();
+    let title = Overlay {
+        text: CUBE_TITLE.to_string(),
+        motion: Motion::Bounce,
+        speed: 1.0,
+        style: Style::new(pancurses::A_BOLD, true),
+    };
+    let title_mesh = match config.cube_title {
+        TitleStyle::Mesh => {
+            let strokes = config.stroke_font.render(CUBE_TITLE)?;
+            Some(strokes.extrude(TITLE_MESH_WIDTH / strokes.width.max(1.0), TITLE_MESH_DEPTH)?)
+        }
+        TitleStyle::Block => {
+            let columns = config
+                .font
+                .render(CUBE_TITLE)
+                .iter()
+                .map(|row| row.chars().count())
+                .max()
+                .unwrap_or(0);
+            Some(config.font.extrude(
+                CUBE_TITLE,
+                TITLE_MESH_WIDTH / columns.max(1) as f32,
+                TITLE_MESH_DEPTH,
+            )?)
+        }
+        _ => None,
+    }
+    .map(|mesh| IndexedMesh::from_mesh(&mesh));
+
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut timestep = FixedTimestep::new(CUBE_STEP_SECONDS, clock.max_delta());
 
     let mut t: f32 = 0.0;
     let mut clear = true;
 
-    while t < 10.0 {
-        cube.rotate_y(t)
-            .rotate_z(t)
-            .translate_z(2.5)
-            .project()
-            .draw(window);
-
-        draw_text(window, t);
+    while t < 10.0 && !input::quit_requested() {
+        let theta = t * config.cube_spin;
+
+        cube.draw(
+            window,
+            |position| {
+                position
+                    .rotate_y(theta)
+                    .rotate_z(theta)
+                    .translate_z(config.cube_distance)
+                    .project()
+            },
+            &mut screen,
+        )?;
+
+        match config.cube_title {
+            TitleStyle::Banner => title.draw(window, &config.font, t),
+            TitleStyle::Vector => draw_vector_title(window, &config.stroke_font, t)?,
+            TitleStyle::Mesh | TitleStyle::Block => {
+                if let Some(mesh) = &title_mesh {
+                    mesh.draw(
+                        window,
+                        |position| {
+                            position
+                                .rotate_x(theta)
+                                .translate_y(TITLE_MESH_OFFSET)
+                                .translate_z(config.cube_distance)
+                                .project()
+                        },
+                        &mut screen,
+                    )?;
+                }
+            }
+        }
+        overlay::draw_configured(window, config, clock.elapsed());
 
         // Move cursor out of the way
         unsafe {
             window.mv(crate::SCREEN_HEIGHT as i32, crate::SCREEN_WIDTH as i32);
         }
 
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        window.refresh();
+        if clear {
+            window.erase();
+        }
+
+        limiter.wait();
+        input::discard(window);
 
-        t += 0.005;
+        timestep.advance(clock.tick());
+        while timestep.next_step() {
+            t += CUBE_STEP_THETA;
+        }
 
         if t > 5.0 {
             clear = false;
         }
+    }
 
-        window.refresh();
-        if clear {
-            window.erase();
-        }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(mesh: &Mesh) -> Vec<[f32; 3]> {
+        mesh.triangles
+            .iter()
+            .flat_map(|triangle| triangle.vertices.iter())
+            .map(|vertex| [vertex.position.x, vertex.position.y, vertex.position.z])
+            .collect()
+    }
+
+    #[test]
+    fn cube_shares_its_corners() {
+        let cube = IndexedMesh::from_mesh(&create_cube());
+
+        assert_eq!(cube.vertices.len(), 8);
+        assert_eq!(cube.indices.len(), 12);
+    }
+
+    #[test]
+    fn conversion_keeps_the_triangles() {
+        let cube = create_cube();
+        let indexed = IndexedMesh::from_mesh(&cube);
+        let converted = indexed.to_mesh();
+
+        assert_eq!(positions(&converted), positions(&cube));
+
+        let again = IndexedMesh::from_mesh(&converted);
+        assert_eq!(again.indices, indexed.indices);
+        assert_eq!(again.vertices.len(), indexed.vertices.len());
+    }
+
+    #[test]
+    fn negative_zero_is_the_same_corner() {
+        let vertex = |x: f32| Vertex {
+            position: Vec3 { x, y: 1.0, z: 1.0 },
+        };
+        let mesh = Mesh::from_vertices(vec![
+            vertex(0.0),
+            vertex(1.0),
+            vertex(2.0),
+            vertex(-0.0),
+            vertex(2.0),
+            vertex(3.0),
+        ])
+        .unwrap();
+
+        let indexed = IndexedMesh::from_mesh(&mesh);
+        assert_eq!(indexed.vertices.len(), 4);
+        assert_eq!(indexed.indices, vec![[0, 1, 2], [0, 2, 3]]);
     }
 }
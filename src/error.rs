@@ -0,0 +1,34 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+// Everything that can go wrong while setting up or running the demo
+#[derive(Debug)]
+pub enum DemoError {
+    // Invalid command line arguments or scene file entries
+    Config(String),
+    // A file that could not be read
+    Io { path: PathBuf, error: io::Error },
+    // A plasma formula that does not parse
+    Formula(String),
+    // Geometry that can't be turned into a mesh
+    InvalidMesh(String),
+    // An effect failed to draw a frame
+    Render(String),
+}
+
+impl fmt::Display for DemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DemoError::Config(message) => write!(f, "{message}"),
+            DemoError::Io { path, error } => {
+                write!(f, "could not read {}: {error}", path.display())
+            }
+            DemoError::Formula(message) => write!(f, "invalid formula: {message}"),
+            DemoError::InvalidMesh(message) => write!(f, "invalid mesh: {message}"),
+            DemoError::Render(message) => write!(f, "rendering failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DemoError {}
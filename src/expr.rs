@@ -0,0 +1,497 @@
+use crate::error::DemoError;
+
+// Deepest nesting of parentheses, function calls and operators a formula may have
+const MAX_NESTING: usize = 256;
+
+/*
+A small math expression language for user supplied plasma formulas, e.g.
+
+    sin(x / 8 - cos(t / 2)) * 128 + sqrt(x*x + y*y)
+
+The source is parsed once and compiled into postfix bytecode, so evaluating it
+for every cell of every frame is a tight loop over a few operations.
+
+Variables: x, y (cell), t (time in seconds), w, h (field size)
+Constants: pi, e
+Operators: + - * / % ^ and unary minus
+Functions: sin cos tan asin acos atan sqrt abs floor ceil round exp ln fract
+           min max pow atan2 hypot
+*/
+
+// The values an expression can refer to while being evaluated
+#[derive(Clone, Copy, Default)]
+pub struct Vars {
+    pub x: f32,
+    pub y: f32,
+    pub t: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Var {
+    X,
+    Y,
+    T,
+    W,
+    H,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Const(f32),
+    Load(Var),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Call1(fn(f32) -> f32),
+    Call2(fn(f32, f32) -> f32),
+}
+
+enum Function {
+    Unary(fn(f32) -> f32),
+    Binary(fn(f32, f32) -> f32),
+}
+
+fn lookup_function(name: &str) -> Option<Function> {
+    Some(match name {
+        "sin" => Function::Unary(f32::sin),
+        "cos" => Function::Unary(f32::cos),
+        "tan" => Function::Unary(f32::tan),
+        "asin" => Function::Unary(f32::asin),
+        "acos" => Function::Unary(f32::acos),
+        "atan" => Function::Unary(f32::atan),
+        "sqrt" => Function::Unary(f32::sqrt),
+        "abs" => Function::Unary(f32::abs),
+        "floor" => Function::Unary(f32::floor),
+        "ceil" => Function::Unary(f32::ceil),
+        "round" => Function::Unary(f32::round),
+        "exp" => Function::Unary(f32::exp),
+        "ln" => Function::Unary(f32::ln),
+        "fract" => Function::Unary(f32::fract),
+        "min" => Function::Binary(f32::min),
+        "max" => Function::Binary(f32::max),
+        "pow" => Function::Binary(f32::powf),
+        "atan2" => Function::Binary(f32::atan2),
+        "hypot" => Function::Binary(f32::hypot),
+        _ => return None,
+    })
+}
+
+// A compiled expression
+#[derive(Clone, Debug)]
+pub struct Expr {
+    code: Vec<Op>,
+}
+
+impl Expr {
+    pub fn compile(source: &str) -> Result<Expr, DemoError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            end: source.chars().count(),
+            position: 0,
+            depth: 0,
+            code: Vec::new(),
+        };
+
+        parser.parse_sum()?;
+
+        match parser.tokens.get(parser.position) {
+            None => Ok(Expr { code: parser.code }),
+            Some((offset, token)) => Err(formula_error(
+                *offset,
+                &format!("unexpected {}", token.describe()),
+            )),
+        }
+    }
+
+    // The stack is passed in so it can be reused between evaluations
+    pub fn eval(&self, vars: &Vars, stack: &mut Vec<f32>) -> f32 {
+        stack.clear();
+
+        for op in &self.code {
+            let value = match *op {
+                Op::Const(value) => value,
+                Op::Load(var) => match var {
+                    Var::X => vars.x,
+                    Var::Y => vars.y,
+                    Var::T => vars.t,
+                    Var::W => vars.w,
+                    Var::H => vars.h,
+                },
+                Op::Neg => -pop(stack),
+                Op::Call1(function) => function(pop(stack)),
+                _ => {
+                    let rhs = pop(stack);
+                    let lhs = pop(stack);
+
+                    match *op {
+                        Op::Add => lhs + rhs,
+                        Op::Sub => lhs - rhs,
+                        Op::Mul => lhs * rhs,
+                        Op::Div => lhs / rhs,
+                        Op::Rem => lhs % rhs,
+                        Op::Pow => lhs.powf(rhs),
+                        Op::Call2(function) => function(lhs, rhs),
+                        _ => unreachable!(),
+                    }
+                }
+            };
+
+            stack.push(value);
+        }
+
+        pop(stack)
+    }
+}
+
+// The parser only emits code that keeps the stack balanced
+fn pop(stack: &mut Vec<f32>) -> f32 {
+    stack.pop().unwrap_or(0.0)
+}
+
+fn formula_error(offset: usize, message: &str) -> DemoError {
+    DemoError::Formula(format!("{message} at position {}", offset + 1))
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Symbol(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(value) => format!("number {value}"),
+            Token::Ident(name) => format!("'{name}'"),
+            Token::Symbol(symbol) => format!("'{symbol}'"),
+        }
+    }
+}
+
+// Splits the source into tokens, each paired with its character offset
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, DemoError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let ch = chars[i];
+
+        if ch.is_whitespace() {
+            i += 1;
+        } else if ch.is_ascii_digit() || ch == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| formula_error(start, &format!("invalid number '{text}'")))?;
+            tokens.push((start, Token::Number(value)));
+        } else if ch.is_ascii_alphabetic() || ch == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            tokens.push((start, Token::Ident(chars[start..i].iter().collect())));
+        } else if "+-*/%^(),".contains(ch) {
+            tokens.push((start, Token::Symbol(ch)));
+            i += 1;
+        } else {
+            return Err(formula_error(
+                start,
+                &format!("unexpected character '{ch}'"),
+            ));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Recursive descent parser emitting postfix code while it goes
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    // Offset just past the last character, reported for errors at the end of the input
+    end: usize,
+    position: usize,
+    // Nesting of the expression being parsed, limited so it can't overflow the stack
+    depth: usize,
+    code: Vec<Op>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    // Offset of the current token, or the end of the input
+    fn offset(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some((offset, _)) => *offset,
+            None => self.end,
+        }
+    }
+
+    fn accept(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), DemoError> {
+        if !self.accept(symbol) {
+            return Err(formula_error(
+                self.offset(),
+                &format!("expected '{symbol}'"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // sum = product (('+' | '-') product)*
+    fn parse_sum(&mut self) -> Result<(), DemoError> {
+        self.parse_product()?;
+
+        loop {
+            if self.accept('+') {
+                self.parse_product()?;
+                self.code.push(Op::Add);
+            } else if self.accept('-') {
+                self.parse_product()?;
+                self.code.push(Op::Sub);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // product = unary (('*' | '/' | '%') unary)*
+    fn parse_product(&mut self) -> Result<(), DemoError> {
+        self.parse_unary()?;
+
+        loop {
+            let op = if self.accept('*') {
+                Op::Mul
+            } else if self.accept('/') {
+                Op::Div
+            } else if self.accept('%') {
+                Op::Rem
+            } else {
+                return Ok(());
+            };
+
+            self.parse_unary()?;
+            self.code.push(op);
+        }
+    }
+
+    // unary = '-' unary | power, every level of nesting passes through here
+    fn parse_unary(&mut self) -> Result<(), DemoError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(formula_error(self.offset(), "formula is nested too deeply"));
+        }
+
+        if self.accept('-') {
+            self.parse_unary()?;
+            self.code.push(Op::Neg);
+        } else {
+            self.parse_power()?;
+        }
+
+        self.depth -= 1;
+        Ok(())
+    }
+
+    // power = primary ('^' unary)?, right associative and binding tighter than unary minus
+    fn parse_power(&mut self) -> Result<(), DemoError> {
+        self.parse_primary()?;
+
+        if self.accept('^') {
+            self.parse_unary()?;
+            self.code.push(Op::Pow);
+        }
+
+        Ok(())
+    }
+
+    // primary = number | variable | constant | function '(' arguments ')' | '(' sum ')'
+    fn parse_primary(&mut self) -> Result<(), DemoError> {
+        let offset = self.offset();
+
+        match self.peek().cloned() {
+            Some(Token::Number(value)) => {
+                self.position += 1;
+                self.code.push(Op::Const(value));
+            }
+            Some(Token::Ident(name)) => {
+                self.position += 1;
+                self.parse_identifier(&name, offset)?;
+            }
+            Some(Token::Symbol('(')) => {
+                self.position += 1;
+                self.parse_sum()?;
+                self.expect(')')?;
+            }
+            Some(token) => {
+                return Err(formula_error(
+                    offset,
+                    &format!("unexpected {}", token.describe()),
+                ))
+            }
+            None => return Err(formula_error(offset, "unexpected end of formula")),
+        }
+
+        Ok(())
+    }
+
+    fn parse_identifier(&mut self, name: &str, offset: usize) -> Result<(), DemoError> {
+        let op = match name {
+            "x" => Op::Load(Var::X),
+            "y" => Op::Load(Var::Y),
+            "t" => Op::Load(Var::T),
+            "w" => Op::Load(Var::W),
+            "h" => Op::Load(Var::H),
+            "pi" => Op::Const(std::f32::consts::PI),
+            "e" => Op::Const(std::f32::consts::E),
+            _ => {
+                let function = lookup_function(name)
+                    .ok_or_else(|| formula_error(offset, &format!("unknown name '{name}'")))?;
+
+                self.expect('(')?;
+                self.parse_sum()?;
+
+                let op = match function {
+                    Function::Unary(function) => Op::Call1(function),
+                    Function::Binary(function) => {
+                        self.expect(',')?;
+                        self.parse_sum()?;
+                        Op::Call2(function)
+                    }
+                };
+
+                self.expect(')')?;
+                op
+            }
+        };
+
+        self.code.push(op);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> f32 {
+        let vars = Vars {
+            x: 10.0,
+            y: 3.0,
+            t: 2.0,
+            ..Vars::default()
+        };
+        Expr::compile(source).unwrap().eval(&vars, &mut Vec::new())
+    }
+
+    fn error(source: &str) -> String {
+        match Expr::compile(source) {
+            Ok(_) => panic!("'{source}' compiled"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn power_binds_tighter_than_unary_minus() {
+        assert_eq!(eval("-2^2"), -4.0);
+        assert_eq!(eval("2^-1"), 0.5);
+        assert_eq!(eval("-2^-2"), -0.25);
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(eval("2^3^2"), 512.0);
+    }
+
+    #[test]
+    fn operators_are_left_associative() {
+        assert_eq!(eval("x-y-t"), 5.0);
+        assert_eq!(eval("x/y/t"), 10.0 / 3.0 / 2.0);
+        assert_eq!(eval("x-y*t"), 4.0);
+        assert_eq!(eval("(x-y)*t"), 14.0);
+    }
+
+    #[test]
+    fn functions_take_their_number_of_arguments() {
+        assert_eq!(eval("min(x, y)"), 3.0);
+        assert_eq!(eval("sin(0)"), 0.0);
+        assert_eq!(
+            error("min(1)"),
+            "invalid formula: expected ',' at position 6"
+        );
+        assert_eq!(
+            error("sin(1,2)"),
+            "invalid formula: expected ')' at position 6"
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_character() {
+        assert_eq!(
+            error("x + $"),
+            "invalid formula: unexpected character '$' at position 5"
+        );
+        assert_eq!(
+            error("x + "),
+            "invalid formula: unexpected end of formula at position 5"
+        );
+        assert_eq!(
+            error("sin x"),
+            "invalid formula: expected '(' at position 5"
+        );
+        assert_eq!(
+            error("x y"),
+            "invalid formula: unexpected 'y' at position 3"
+        );
+        assert_eq!(
+            error("1 + foo(2)"),
+            "invalid formula: unknown name 'foo' at position 5"
+        );
+        assert_eq!(
+            error("(x + 1"),
+            "invalid formula: expected ')' at position 7"
+        );
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize, open: &str, close: &str| {
+            format!("{}x{}", open.repeat(depth), close.repeat(depth))
+        };
+
+        assert_eq!(eval(&nested(100, "(", ")")), 10.0);
+        assert_eq!(eval(&nested(100, "-", "")), 10.0);
+        assert_eq!(eval(&nested(100, "abs(", ")")), 10.0);
+
+        assert_eq!(
+            error(&nested(10_000, "(", ")")),
+            "invalid formula: formula is nested too deeply at position 257"
+        );
+        assert!(error(&nested(10_000, "-", "")).contains("nested too deeply"));
+        assert!(error(&nested(10_000, "sin(", ")")).contains("nested too deeply"));
+        assert!(error(&"2^".repeat(10_000)).contains("nested too deeply"));
+    }
+}
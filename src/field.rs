@@ -0,0 +1,99 @@
+// Fewest cells a thread of fill_rows gets, smaller fields are filled by fewer threads
+const MIN_BAND_CELLS: usize = 8192;
+
+// A two dimensional grid of cells stored row by row
+#[derive(Clone)]
+pub struct Field<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Default> Field<T> {
+    pub fn new(width: usize, height: usize) -> Field<T> {
+        Field {
+            width,
+            height,
+            cells: vec![T::default(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &T {
+        &self.cells[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        let index = self.index(x, y);
+        self.cells[index] = value;
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    // Changes the dimensions, resetting all cells if they differ from the current ones
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width != self.width || height != self.height {
+            *self = Field::new(width, height);
+        }
+    }
+
+    // Calls fill_row(y, row) for every row. The rows are split into one band per
+    // thread, so with more than one thread the bands are filled in parallel.
+    // Small fields use fewer threads, as starting one costs more than it saves.
+    pub fn fill_rows<F>(&mut self, threads: usize, fill_row: F)
+    where
+        T: Send,
+        F: Fn(usize, &mut [T]) + Sync,
+    {
+        let width = self.width.max(1);
+
+        let Some(band_height) = self.band_height(threads) else {
+            for (y, row) in self.cells.chunks_mut(width).enumerate() {
+                fill_row(y, row);
+            }
+            return;
+        };
+
+        let fill_row = &fill_row;
+
+        std::thread::scope(|scope| {
+            for (band, cells) in self.cells.chunks_mut(band_height * width).enumerate() {
+                scope.spawn(move || {
+                    for (i, row) in cells.chunks_mut(width).enumerate() {
+                        fill_row(band * band_height + i, row);
+                    }
+                });
+            }
+        });
+    }
+
+    // How many threads fill_rows really runs on when given the number of threads
+    pub fn fill_threads(&self, threads: usize) -> usize {
+        self.band_height(threads)
+            .map_or(1, |band_height| self.height.div_ceil(band_height))
+    }
+
+    // Rows per band of fill_rows, None if the field is filled on the calling thread
+    fn band_height(&self, threads: usize) -> Option<usize> {
+        let threads = threads.min(self.cells.len() / MIN_BAND_CELLS);
+
+        match threads <= 1 || self.height <= 1 {
+            true => None,
+            false => Some(self.height.div_ceil(threads)),
+        }
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        debug_assert!(x < self.width && y < self.height);
+        y * self.width + x
+    }
+}
@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::cube3d::{Mesh, Vec3, Vertex};
+use crate::error::DemoError;
+
+// The font banners are drawn with unless another one is loaded
+const BUILTIN_FONT: &str = include_str!("../fonts/demo.flf");
+
+// Horizontal smushing rules, as numbered in the FIGfont specification
+const SMUSH_EQUAL: u32 = 1;
+const SMUSH_UNDERSCORE: u32 = 2;
+const SMUSH_HIERARCHY: u32 = 4;
+const SMUSH_OPPOSITE_PAIR: u32 = 8;
+const SMUSH_BIG_X: u32 = 16;
+const SMUSH_HARDBLANK: u32 = 32;
+const SMUSH_RULES: u32 = 63;
+
+// Bits of the full layout header field selecting the horizontal layout
+const FULL_LAYOUT_FITTING: u32 = 64;
+const FULL_LAYOUT_SMUSHING: u32 = 128;
+
+// Characters every font defines after the ASCII ones, before any code tagged ones
+const DEUTSCH_CHARACTERS: [char; 7] = ['Ä', 'Ö', 'Ü', 'ä', 'ö', 'ü', 'ß'];
+
+// Character classes of the hierarchy rule, later classes replace earlier ones
+const HIERARCHY: [&str; 6] = ["|", "/\\", "[]", "{}", "()", "<>"];
+
+// How far the characters of a banner are moved together
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    // Every character keeps its full width
+    FullWidth,
+    // Characters are moved together until they touch
+    Fitting,
+    // Characters are moved one column further, merging the touching characters by the font's rules
+    Smushing,
+}
+
+impl FromStr for Layout {
+    type Err = DemoError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "full" => Ok(Layout::FullWidth),
+            "fit" => Ok(Layout::Fitting),
+            "smush" => Ok(Layout::Smushing),
+            _ => Err(DemoError::Config(format!("unknown font layout '{name}'"))),
+        }
+    }
+}
+
+/*
+A FIGlet font (.flf): a header line like "flf2a$ 6 5 16 15 13 0 24463", some
+comment lines, then every character as rows ending with an end mark, the last
+row of a character with two. The hard blank ('$' above) is drawn as a space
+but never smushed away.
+*/
+#[derive(Clone, Debug)]
+pub struct Font {
+    height: usize,
+    hardblank: char,
+    glyphs: HashMap<char, Vec<Vec<char>>>,
+    smush_rules: u32,
+    pub layout: Layout,
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::parse(BUILTIN_FONT).expect("the built-in font is valid")
+    }
+}
+
+impl Font {
+    pub fn load(path: &Path) -> Result<Font, DemoError> {
+        let source = std::fs::read_to_string(path).map_err(|error| DemoError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        Font::parse(&source)
+            .map_err(|error| DemoError::Config(format!("font {}: {error}", path.display())))
+    }
+
+    pub fn parse(source: &str) -> Result<Font, DemoError> {
+        let mut lines = source.lines();
+        let header = lines.next().unwrap_or("");
+        let invalid_header = || DemoError::Config(format!("invalid header '{header}'"));
+
+        let mut fields = header.split_whitespace();
+        let hardblank = fields
+            .next()
+            .and_then(|signature| signature.strip_prefix("flf2a"))
+            .and_then(|rest| rest.chars().next())
+            .ok_or_else(invalid_header)?;
+
+        let numbers: Vec<i64> = fields
+            .map(|field| field.parse().map_err(|_| invalid_header()))
+            .collect::<Result<_, _>>()?;
+        if numbers.len() < 5 || numbers[0] < 1 || numbers[4] < 0 {
+            return Err(invalid_header());
+        }
+
+        let height = numbers[0] as usize;
+        let old_layout = numbers[3];
+        let comment_lines = numbers[4] as usize;
+
+        // The full layout field replaces the old one if it is given
+        let (layout, smush_rules) = match numbers.get(6) {
+            Some(&full_layout) => {
+                let full_layout = full_layout as u32;
+                let layout = if full_layout & FULL_LAYOUT_SMUSHING != 0 {
+                    Layout::Smushing
+                } else if full_layout & FULL_LAYOUT_FITTING != 0 {
+                    Layout::Fitting
+                } else {
+                    Layout::FullWidth
+                };
+                (layout, full_layout & SMUSH_RULES)
+            }
+            None => match old_layout {
+                -1 => (Layout::FullWidth, 0),
+                0 => (Layout::Fitting, 0),
+                rules => (Layout::Smushing, rules as u32 & SMUSH_RULES),
+            },
+        };
+
+        let mut lines = lines.skip(comment_lines);
+        let mut glyphs = HashMap::new();
+
+        let read_glyph = |lines: &mut dyn Iterator<Item = &str>| -> Option<Vec<Vec<char>>> {
+            let mut rows: Vec<Vec<char>> = Vec::with_capacity(height);
+            for _ in 0..height {
+                let line = lines.next()?.trim_end();
+                let end_mark = line.chars().last().unwrap_or(' ');
+                rows.push(line.trim_end_matches(end_mark).chars().collect());
+            }
+
+            // Rows are padded to the same width, so columns line up when merging
+            let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+            for row in rows.iter_mut() {
+                row.resize(width, ' ');
+            }
+            Some(rows)
+        };
+
+        let required = (' '..='~').chain(DEUTSCH_CHARACTERS);
+        for character in required {
+            match read_glyph(&mut lines) {
+                Some(glyph) => glyphs.insert(character, glyph),
+                None => break,
+            };
+        }
+
+        // Code tagged characters start with their code in decimal, octal (0...) or hex (0x...)
+        while let Some(tag) = lines.next() {
+            let code = tag.split_whitespace().next().unwrap_or("");
+            let code = match code.strip_prefix("0x").or(code.strip_prefix("0X")) {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None if code.len() > 1 && code.starts_with('0') => u32::from_str_radix(code, 8),
+                None => code.parse(),
+            };
+
+            match (code.ok().and_then(char::from_u32), read_glyph(&mut lines)) {
+                (Some(character), Some(glyph)) => glyphs.insert(character, glyph),
+                _ => break,
+            };
+        }
+
+        if !glyphs.contains_key(&' ') {
+            return Err(DemoError::Config("font has no characters".to_string()));
+        }
+
+        Ok(Font {
+            height,
+            hardblank,
+            glyphs,
+            smush_rules,
+            layout,
+        })
+    }
+
+    // Renders a line of text into rows of banner text, characters the font lacks are left out
+    pub fn render(&self, text: &str) -> Vec<String> {
+        let mut rows: Vec<Vec<char>> = vec![Vec::new(); self.height];
+
+        for glyph in text
+            .chars()
+            .filter_map(|character| self.glyphs.get(&character))
+        {
+            let overlap = self.overlap(&rows, glyph);
+
+            for (row, glyph_row) in rows.iter_mut().zip(glyph) {
+                let start = row.len() - overlap;
+                for (i, &right) in glyph_row.iter().enumerate() {
+                    match row.get(start + i) {
+                        Some(&left) => row[start + i] = self.smush(left, right).unwrap_or(right),
+                        None => row.push(right),
+                    }
+                }
+            }
+        }
+
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&character| match character == self.hardblank {
+                        true => ' ',
+                        false => character,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /*
+    Builds a solid mesh of a banner: every character that isn't blank is a
+    square cell of the given size, each row of touching cells gets a front and
+    a back cap, and every cell edge next to a blank a side wall. The banner is
+    depth thick and centered on the origin.
+    */
+    pub(crate) fn extrude(&self, text: &str, size: f32, depth: f32) -> Result<Mesh, DemoError> {
+        let rows: Vec<Vec<bool>> = self
+            .render(text)
+            .iter()
+            .map(|row| row.chars().map(|character| character != ' ').collect())
+            .collect();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let height = rows.len();
+
+        let filled = |x: i32, y: i32| -> bool {
+            x >= 0
+                && y >= 0
+                && rows.get(y as usize).and_then(|row| row.get(x as usize)) == Some(&true)
+        };
+        let corner = |x: i32, y: i32, z: f32| Vertex {
+            position: Vec3 {
+                x: (x as f32 - width as f32 / 2.0) * size,
+                y: (y as f32 - height as f32 / 2.0) * size,
+                z,
+            },
+        };
+
+        let (front, back) = (-depth / 2.0, depth / 2.0);
+        let mut vertices = Vec::new();
+
+        // Two triangles of a quad, its corners given counterclockwise as seen from outside
+        let mut quad = |corners: [Vertex; 4]| {
+            let [a, b, c, d] = corners;
+            vertices.extend([a.clone(), b, c.clone(), a, c, d]);
+        };
+
+        for (y, row) in rows.iter().enumerate() {
+            let y = y as i32;
+            let mut x = 0;
+
+            while x < row.len() {
+                if !row[x] {
+                    x += 1;
+                    continue;
+                }
+
+                let start = x as i32;
+                while x < row.len() && row[x] {
+                    x += 1;
+                }
+                let end = x as i32;
+
+                quad([
+                    corner(start, y, front),
+                    corner(start, y + 1, front),
+                    corner(end, y + 1, front),
+                    corner(end, y, front),
+                ]);
+                quad([
+                    corner(start, y, back),
+                    corner(end, y, back),
+                    corner(end, y + 1, back),
+                    corner(start, y + 1, back),
+                ]);
+            }
+
+            for x in 0..row.len() as i32 {
+                if !filled(x, y) {
+                    continue;
+                }
+
+                if !filled(x, y - 1) {
+                    quad([
+                        corner(x, y, front),
+                        corner(x + 1, y, front),
+                        corner(x + 1, y, back),
+                        corner(x, y, back),
+                    ]);
+                }
+                if !filled(x, y + 1) {
+                    quad([
+                        corner(x, y + 1, front),
+                        corner(x, y + 1, back),
+                        corner(x + 1, y + 1, back),
+                        corner(x + 1, y + 1, front),
+                    ]);
+                }
+                if !filled(x - 1, y) {
+                    quad([
+                        corner(x, y, front),
+                        corner(x, y, back),
+                        corner(x, y + 1, back),
+                        corner(x, y + 1, front),
+                    ]);
+                }
+                if !filled(x + 1, y) {
+                    quad([
+                        corner(x + 1, y, front),
+                        corner(x + 1, y + 1, front),
+                        corner(x + 1, y + 1, back),
+                        corner(x + 1, y, back),
+                    ]);
+                }
+            }
+        }
+
+        Mesh::from_vertices(vertices)
+    }
+
+    // How many columns a glyph can be moved into the banner rendered so far
+    fn overlap(&self, rows: &[Vec<char>], glyph: &[Vec<char>]) -> usize {
+        if self.layout == Layout::FullWidth {
+            return 0;
+        }
+
+        let width = rows.first().map_or(0, |row| row.len());
+        let mut overlap = glyph.first().map_or(0, |row| row.len()).min(width);
+
+        for (row, glyph_row) in rows.iter().zip(glyph) {
+            // Blank columns at the end of the row and at the start of the glyph row
+            let (trailing, left) = match row.iter().rposition(|&character| character != ' ') {
+                Some(end) => (row.len() - 1 - end, row[end]),
+                None => (row.len(), ' '),
+            };
+            let (leading, right) = match glyph_row.iter().position(|&character| character != ' ') {
+                Some(start) => (start, glyph_row[start]),
+                None => (glyph_row.len(), ' '),
+            };
+
+            let mut amount = trailing + leading;
+            if left != ' ' && right != ' ' && self.smush(left, right).is_some() {
+                amount += 1;
+            }
+
+            overlap = overlap.min(amount);
+        }
+
+        overlap
+    }
+
+    // The character two touching characters merge into, None if they can't be merged
+    fn smush(&self, left: char, right: char) -> Option<char> {
+        if left == ' ' {
+            return Some(right);
+        }
+        if right == ' ' {
+            return Some(left);
+        }
+        if self.layout != Layout::Smushing {
+            return None;
+        }
+
+        let hardblank = self.hardblank;
+        let rules = self.smush_rules;
+
+        // Without any rules the right character simply replaces the left one
+        if rules == 0 {
+            return match (left == hardblank, right == hardblank) {
+                (true, _) => Some(right),
+                (_, true) => Some(left),
+                _ => Some(right),
+            };
+        }
+
+        if left == hardblank || right == hardblank {
+            return match left == right && rules & SMUSH_HARDBLANK != 0 {
+                true => Some(hardblank),
+                false => None,
+            };
+        }
+
+        if rules & SMUSH_EQUAL != 0 && left == right {
+            return Some(left);
+        }
+
+        if rules & SMUSH_UNDERSCORE != 0 {
+            const REPLACING: &str = "|/\\[]{}()<>";
+            if left == '_' && REPLACING.contains(right) {
+                return Some(right);
+            }
+            if right == '_' && REPLACING.contains(left) {
+                return Some(left);
+            }
+        }
+
+        if rules & SMUSH_HIERARCHY != 0 {
+            let class =
+                |character: char| HIERARCHY.iter().position(|class| class.contains(character));
+            if let (Some(left_class), Some(right_class)) = (class(left), class(right)) {
+                if left_class != right_class {
+                    return Some(match left_class > right_class {
+                        true => left,
+                        false => right,
+                    });
+                }
+            }
+        }
+
+        if rules & SMUSH_OPPOSITE_PAIR != 0
+            && matches!(
+                (left, right),
+                ('[', ']') | (']', '[') | ('{', '}') | ('}', '{') | ('(', ')') | (')', '(')
+            )
+        {
+            return Some('|');
+        }
+
+        if rules & SMUSH_BIG_X != 0 {
+            match (left, right) {
+                ('/', '\\') => return Some('|'),
+                ('\\', '/') => return Some('Y'),
+                ('>', '<') => return Some('X'),
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+    A font one row high whose glyphs are the characters themselves, except for
+    '/' and '\' with blanks to move into, followed by the given tagged glyphs.
+    */
+    fn source(header: &str, tagged: &str) -> String {
+        let mut source = format!("{header}\n");
+        for character in (' '..='~').chain(DEUTSCH_CHARACTERS) {
+            let (row, end_mark) = match character {
+                '/' => ("/ ".to_string(), '@'),
+                '\\' => (" \\ ".to_string(), '@'),
+                '@' => ("@".to_string(), '#'),
+                character => (character.to_string(), '@'),
+            };
+            source += &format!("{row}{end_mark}{end_mark}\n");
+        }
+        source + tagged
+    }
+
+    fn font(header: &str) -> Font {
+        Font::parse(&source(header, "")).unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        Font::parse(source).unwrap_err().to_string()
+    }
+
+    fn smusher(rules: u32) -> Font {
+        Font {
+            height: 1,
+            hardblank: '$',
+            glyphs: HashMap::new(),
+            smush_rules: rules,
+            layout: Layout::Smushing,
+        }
+    }
+
+    #[test]
+    fn headers_are_parsed() {
+        let font = font("flf2a$ 1 1 10 -1 2\nfirst comment\nsecond comment");
+        assert_eq!((font.height, font.hardblank), (1, '$'));
+        assert_eq!(font.render("Hi!"), ["Hi!"]);
+
+        for header in [
+            "flf2b$ 1 1 10 -1 0",
+            "flf2a$ 0 1 10 -1 0",
+            "flf2a$ 1 1 10 -1",
+            "flf2a$ 1 1 x -1 0",
+        ] {
+            assert_eq!(
+                error(&source(header, "")),
+                format!("invalid header '{header}'")
+            );
+        }
+        assert_eq!(error("flf2a$ 1 1 10 -1 0\n"), "font has no characters");
+    }
+
+    #[test]
+    fn full_layout_replaces_old_layout() {
+        let layout = |header: &str| {
+            let font = font(header);
+            (font.layout, font.smush_rules)
+        };
+
+        assert_eq!(layout("flf2a$ 1 1 10 -1 0"), (Layout::FullWidth, 0));
+        assert_eq!(layout("flf2a$ 1 1 10 0 0"), (Layout::Fitting, 0));
+        assert_eq!(layout("flf2a$ 1 1 10 15 0"), (Layout::Smushing, 15));
+        assert_eq!(layout("flf2a$ 1 1 10 15 0 0 0"), (Layout::FullWidth, 0));
+        assert_eq!(layout("flf2a$ 1 1 10 -1 0 0 65"), (Layout::Fitting, 1));
+        assert_eq!(layout("flf2a$ 1 1 10 0 0 0 144"), (Layout::Smushing, 16));
+    }
+
+    #[test]
+    fn layouts_move_characters_together() {
+        assert_eq!(font("flf2a$ 1 1 10 -1 0").render("a/\\"), ["a/  \\ "]);
+        assert_eq!(font("flf2a$ 1 1 10 0 0").render("a/\\"), ["a/\\ "]);
+        assert_eq!(font("flf2a$ 1 1 10 16 0").render("a/\\"), ["a| "]);
+        assert_eq!(font("flf2a$ 1 1 10 -1 0 0 144").render("a/\\"), ["a| "]);
+    }
+
+    #[test]
+    fn every_smushing_rule_merges_its_characters() {
+        let cases = [
+            (SMUSH_EQUAL, '|', '|', '|'),
+            (SMUSH_UNDERSCORE, '_', '/', '/'),
+            (SMUSH_UNDERSCORE, '|', '_', '|'),
+            (SMUSH_HIERARCHY, '|', '/', '/'),
+            (SMUSH_HIERARCHY, '}', '[', '}'),
+            (SMUSH_OPPOSITE_PAIR, '[', ']', '|'),
+            (SMUSH_OPPOSITE_PAIR, ')', '(', '|'),
+            (SMUSH_BIG_X, '/', '\\', '|'),
+            (SMUSH_BIG_X, '\\', '/', 'Y'),
+            (SMUSH_BIG_X, '>', '<', 'X'),
+            (SMUSH_HARDBLANK, '$', '$', '$'),
+        ];
+
+        for (rule, left, right, merged) in cases {
+            assert_eq!(smusher(rule).smush(left, right), Some(merged));
+            // None of the other rules merges the same pair
+            assert_eq!(smusher(SMUSH_RULES & !rule).smush(left, right), None);
+        }
+
+        // Without rules the right character wins, but never over a hard blank
+        assert_eq!(smusher(0).smush('a', 'b'), Some('b'));
+        assert_eq!(smusher(0).smush('a', '$'), Some('a'));
+        assert_eq!(smusher(SMUSH_RULES).smush('a', 'b'), None);
+    }
+
+    #[test]
+    fn code_tagged_characters_follow_the_required_ones() {
+        let tagged = "0x263A  WHITE SMILING FACE\n:)@@\n0102 octal\nB8@@\n8364 EURO\nEUR@@\n";
+        let font = Font::parse(&source("flf2a$ 1 1 10 -1 0", tagged)).unwrap();
+
+        assert_eq!(font.render("ß"), ["ß"]);
+        assert_eq!(font.render("\u{263A}"), [":)"]);
+        assert_eq!(font.render("B"), ["B8"]);
+        assert_eq!(font.render("€"), ["EUR"]);
+
+        // Characters the font lacks are left out
+        assert_eq!(font.render("\u{2603}A"), ["A"]);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::clock::{Clock, FixedTimestep, FrameLimiter};
+use crate::colors;
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::field::Field;
+use crate::input;
+use crate::overlay;
+use crate::rng::Rng;
+use crate::terminal;
+
+// How long the fire effect runs in seconds
+const FIRE_DURATION: f32 = 10.0;
+
+// The fire propagates in fixed steps so it burns at the same pace at any frame rate
+const FIRE_STEP_SECONDS: f32 = 1.0 / 30.0;
+
+// Heat lost over the height of the screen while rising. The cooling per row is
+// derived from it, so the flames reach about two thirds up on any terminal.
+const COOLING: f32 = 1.0;
+
+// Characters from the coolest to the hottest visible heat
+const FLAME_RAMP: [char; 9] = ['.', ':', '^', '*', 'x', 's', 'S', '#', '$'];
+
+/*
+Classic demoscene fire: the hidden bottom row is seeded with random heat every
+step, every cell above takes the average of the cells below it minus some
+cooling, so the heat rises and fades. Wind shifts where the heat is taken from.
+*/
+struct Fire {
+    heat: Field<f32>,
+    rng: Rng,
+    intensity: f32,
+    wind: f32,
+    cooling: f32,
+}
+
+impl Fire {
+    fn resize(&mut self, width: usize, height: usize) {
+        // One extra row below the screen holds the seeds
+        self.heat.resize(width, height + 1);
+        self.cooling = COOLING / height.max(1) as f32;
+    }
+
+    fn step(&mut self) {
+        let width = self.heat.width();
+        let height = self.heat.height();
+
+        if width == 0 || height < 2 {
+            return;
+        }
+
+        for x in 0..width {
+            let seed = match self.rng.next_f32() < self.intensity {
+                true => self.rng.range(0.7, 1.0),
+                false => 0.0,
+            };
+            self.heat.set(x, height - 1, seed);
+        }
+
+        // Top to bottom, so every row still sees the previous heat of the rows below
+        for y in 0..height - 1 {
+            for x in 0..width {
+                let source_x = self.wind_source(x, width);
+                let below = y + 1;
+                let further_below = (y + 2).min(height - 1);
+
+                let heat = (*self.heat.get((source_x + width - 1) % width, below)
+                    + *self.heat.get(source_x, below)
+                    + *self.heat.get((source_x + 1) % width, below)
+                    + *self.heat.get(source_x, further_below))
+                    / 4.0
+                    - self.cooling;
+
+                self.heat.set(x, y, heat.max(0.0));
+            }
+        }
+    }
+
+    // Column the heat of a cell is taken from, drifting against the wind direction
+    fn wind_source(&mut self, x: usize, width: usize) -> usize {
+        if self.rng.next_f32() >= self.wind.abs() {
+            return x;
+        }
+
+        match self.wind > 0.0 {
+            true => (x + width - 1) % width,
+            false => (x + 1) % width,
+        }
+    }
+
+    fn draw(&self, window: &pancurses::Window) {
+        for y in 0..self.heat.height() - 1 {
+            for x in 0..self.heat.width() {
+                let heat = *self.heat.get(x, y);
+
+                // Cells that have cooled down completely stay empty
+                if heat < 0.05 {
+                    window.mvaddch(y as i32, x as i32, ' ');
+                    continue;
+                }
+
+                let glyph = FLAME_RAMP
+                    [((heat * FLAME_RAMP.len() as f32) as usize).min(FLAME_RAMP.len() - 1)];
+                let attributes = match heat {
+                    heat if heat < 0.3 => colors::fg(colors::RED),
+                    heat if heat < 0.55 => colors::fg(colors::RED) | pancurses::A_BOLD,
+                    heat if heat < 0.75 => colors::fg(colors::YELLOW),
+                    _ => colors::fg(colors::YELLOW) | pancurses::A_BOLD,
+                };
+
+                window.attrset(attributes);
+                window.mvaddch(y as i32, x as i32, glyph);
+            }
+        }
+
+        window.attrset(pancurses::A_NORMAL);
+    }
+}
+
+pub fn run_fire_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut timestep = FixedTimestep::new(FIRE_STEP_SECONDS, clock.max_delta());
+
+    let mut fire = Fire {
+        heat: Field::new(0, 0),
+        rng: Rng::new(config.seed),
+        intensity: config.fire_intensity,
+        wind: config.fire_wind,
+        cooling: 0.0,
+    };
+
+    while clock.elapsed() < FIRE_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        fire.resize(screen_width as usize, screen_height as usize);
+
+        timestep.advance(clock.tick());
+        while timestep.next_step() {
+            fire.step();
+        }
+
+        fire.draw(window);
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+    }
+
+    Ok(())
+}
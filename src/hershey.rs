@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cube3d::{draw_line, Mesh, Vec2, Vec3, Vertex};
+use crate::error::DemoError;
+use crate::CELL_ASPECT;
+
+// Glyphs the vector titles are drawn with unless another font is loaded. It only
+// covers the upper case letters, digits and most punctuation, but not $ % & @ ^ `
+// { } ~ or lower case letters.
+const BUILTIN_FONT: &str = include_str!("../fonts/simplex.jhf");
+
+// Coordinates are stored as characters relative to 'R', " R" lifts the pen
+const ORIGIN: i32 = 'R' as i32;
+const PEN_UP: (i32, i32) = (' ' as i32 - ORIGIN, 0);
+
+// The pen strokes of a character and how far it reaches to the left and right of its origin
+#[derive(Clone, Debug)]
+struct Glyph {
+    left: i32,
+    right: i32,
+    strokes: Vec<Vec<(i32, i32)>>,
+}
+
+/*
+A Hershey font in the .jhf format: every glyph is a record starting with a
+five digit number and a three digit count of coordinate pairs, followed by
+the pairs as characters relative to 'R'. The first pair holds the left and
+right edge of the glyph, the others are the points of its strokes, y growing
+downwards. Records longer than a line continue on the next one. Glyphs are
+mapped to the printable ASCII characters in the order they appear in, records
+without strokes leave their character out of the font unless it is the space.
+*/
+#[derive(Clone, Debug)]
+pub struct StrokeFont {
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl Default for StrokeFont {
+    fn default() -> Self {
+        StrokeFont::parse(BUILTIN_FONT).expect("the built-in stroke font is valid")
+    }
+}
+
+// Line segments of a rendered text in font units, centered on the origin
+pub(crate) struct Strokes {
+    pub(crate) segments: Vec<(Vec2, Vec2)>,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+impl StrokeFont {
+    pub fn load(path: &Path) -> Result<StrokeFont, DemoError> {
+        let source = std::fs::read_to_string(path).map_err(|error| DemoError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        StrokeFont::parse(&source)
+            .map_err(|error| DemoError::Config(format!("stroke font {}: {error}", path.display())))
+    }
+
+    pub fn parse(source: &str) -> Result<StrokeFont, DemoError> {
+        let mut glyphs = HashMap::new();
+        let mut lines = source.lines().filter(|line| !line.trim().is_empty());
+        let mut characters = ' '..='~';
+
+        while let Some(line) = lines.next() {
+            let invalid = || DemoError::Config(format!("invalid glyph record '{line}'"));
+
+            let count: usize = line
+                .get(5..8)
+                .and_then(|count| count.trim().parse().ok())
+                .filter(|&count| count > 0)
+                .ok_or_else(invalid)?;
+
+            let mut data: Vec<char> = line[8..].chars().collect();
+            while data.len() < count * 2 {
+                match lines.next() {
+                    Some(continued) => data.extend(continued.chars()),
+                    None => return Err(invalid()),
+                }
+            }
+
+            let mut pairs = data[..count * 2]
+                .chunks(2)
+                .map(|pair| (pair[0] as i32 - ORIGIN, pair[1] as i32 - ORIGIN));
+            let (left, right) = pairs.next().ok_or_else(invalid)?;
+
+            let mut strokes = vec![Vec::new()];
+            for point in pairs {
+                match point == PEN_UP {
+                    true => strokes.push(Vec::new()),
+                    false => strokes.last_mut().unwrap().push(point),
+                }
+            }
+            strokes.retain(|stroke| !stroke.is_empty());
+
+            let Some(character) = characters.next() else {
+                break;
+            };
+            if character == ' ' || !strokes.is_empty() {
+                glyphs.insert(
+                    character,
+                    Glyph {
+                        left,
+                        right,
+                        strokes,
+                    },
+                );
+            }
+        }
+
+        if !glyphs.contains_key(&' ') {
+            return Err(DemoError::Config("font has no characters".to_string()));
+        }
+
+        Ok(StrokeFont { glyphs })
+    }
+
+    // Lays out a line of text, failing on the first character the font lacks
+    pub(crate) fn render(&self, text: &str) -> Result<Strokes, DemoError> {
+        let mut segments = Vec::new();
+        let mut pen = 0;
+        let (mut top, mut bottom) = (i32::MAX, i32::MIN);
+
+        for character in text.chars() {
+            let glyph = self.glyphs.get(&character).ok_or_else(|| {
+                DemoError::Render(format!("the stroke font has no glyph for '{character}'"))
+            })?;
+
+            let origin = pen - glyph.left;
+            for stroke in &glyph.strokes {
+                let points: Vec<Vec2> = stroke
+                    .iter()
+                    .map(|&(x, y)| {
+                        top = top.min(y);
+                        bottom = bottom.max(y);
+                        Vec2 {
+                            x: (origin + x) as f32,
+                            y: y as f32,
+                        }
+                    })
+                    .collect();
+
+                // A single point is drawn as a dot
+                match points.len() {
+                    1 => segments.push((points[0].clone(), points[0].clone())),
+                    _ => segments.extend(
+                        points
+                            .windows(2)
+                            .map(|pair| (pair[0].clone(), pair[1].clone())),
+                    ),
+                }
+            }
+            pen += glyph.right - glyph.left;
+        }
+
+        if segments.is_empty() {
+            return Ok(Strokes {
+                segments,
+                width: pen as f32,
+                height: 0.0,
+            });
+        }
+
+        let (center_x, center_y) = (pen as f32 / 2.0, (top + bottom) as f32 / 2.0);
+        let center = |point: &Vec2| Vec2 {
+            x: point.x - center_x,
+            y: point.y - center_y,
+        };
+
+        Ok(Strokes {
+            segments: segments
+                .iter()
+                .map(|(start, end)| (center(start), center(end)))
+                .collect(),
+            width: pen as f32,
+            height: (bottom - top) as f32,
+        })
+    }
+}
+
+impl Strokes {
+    // Draws the text flat on the screen, scale is the number of columns per font unit
+    pub(crate) fn draw(
+        &self,
+        window: &pancurses::Window,
+        center: &Vec2,
+        scale: f32,
+    ) -> Result<(), DemoError> {
+        let screen = |point: &Vec2| Vec2 {
+            x: center.x + point.x * scale,
+            y: center.y + point.y * scale / CELL_ASPECT,
+        };
+
+        self.segments
+            .iter()
+            .try_for_each(|(start, end)| draw_line(window, &screen(start), &screen(end)))
+    }
+
+    /*
+    Turns every segment into a wall of two triangles reaching from the front
+    to the back of the text, so it can be transformed like any other mesh. The
+    text is scaled by scale and is depth thick, centered on the origin.
+    */
+    pub(crate) fn extrude(&self, scale: f32, depth: f32) -> Result<Mesh, DemoError> {
+        let vertex = |point: &Vec2, z: f32| Vertex {
+            position: Vec3 {
+                x: point.x * scale,
+                y: point.y * scale,
+                z,
+            },
+        };
+
+        let (front, back) = (-depth / 2.0, depth / 2.0);
+        let vertices = self
+            .segments
+            .iter()
+            .flat_map(|(start, end)| {
+                [
+                    vertex(start, front),
+                    vertex(end, front),
+                    vertex(end, back),
+                    vertex(start, front),
+                    vertex(end, back),
+                    vertex(start, back),
+                ]
+            })
+            .collect();
+
+        Mesh::from_vertices(vertices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A space and an 'X' of two strokes, the record split over two lines
+    const FONT: &str = "    1  1JZ\n    2  6HXNIVY RVINY\n";
+
+    #[test]
+    fn records_are_decoded_into_strokes() {
+        let font = StrokeFont::parse(FONT).unwrap();
+
+        let space = &font.glyphs[&' '];
+        assert_eq!((space.left, space.right), (-8, 8));
+        assert!(space.strokes.is_empty());
+
+        let bang = &font.glyphs[&'!'];
+        assert_eq!((bang.left, bang.right), (-10, 6));
+        assert_eq!(
+            bang.strokes,
+            [vec![(-4, -9), (4, 7)], vec![(4, -9), (-4, 7)]]
+        );
+
+        // The count covers the data on the continuation line
+        let wrapped = StrokeFont::parse("    1  1JZ\n    2  6HXNIV\nY RVINY\n").unwrap();
+        assert_eq!(wrapped.glyphs[&'!'].strokes, bang.strokes);
+    }
+
+    #[test]
+    fn malformed_records_are_rejected() {
+        let error = |source: &str| StrokeFont::parse(source).unwrap_err().to_string();
+
+        assert_eq!(error("    1  xJZ"), "invalid glyph record '    1  xJZ'");
+        assert_eq!(error("    1  0JZ"), "invalid glyph record '    1  0JZ'");
+        assert_eq!(error("    1  3JZNI"), "invalid glyph record '    1  3JZNI'");
+        assert_eq!(error(""), "font has no characters");
+    }
+
+    #[test]
+    fn missing_glyphs_are_reported() {
+        let font = StrokeFont::parse(FONT).unwrap();
+        assert_eq!(font.render("! !").unwrap().segments.len(), 4);
+        assert_eq!(
+            font.render("!?").err().map(|error| error.to_string()),
+            Some("rendering failed: the stroke font has no glyph for '?'".to_string())
+        );
+
+        let builtin = StrokeFont::default();
+        assert!(builtin.render("= 3D CUBE 0123456789 ABCXYZ!?").is_ok());
+        assert!(builtin.render("a").is_err());
+        assert!(builtin.render("$").is_err());
+    }
+}
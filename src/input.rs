@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use pancurses::Input;
+
+use crate::terminal;
+
+const KEY_ESCAPE: char = '\u{1b}';
+
+// Set once the user asked to end the demo, every effect loop stops on it
+static QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// In loop mode any key press ends the demo, not only 'q' and escape
+static ANY_KEY_QUITS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_any_key_quits(enabled: bool) {
+    ANY_KEY_QUITS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn request_quit() {
+    QUIT_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub fn quit_requested() -> bool {
+    QUIT_REQUESTED.load(Ordering::Relaxed)
+}
+
+/*
+Returns the next pending key press without blocking (the window is in nodelay
+mode). Keys ending the demo are handled here and not passed on to the effect.
+*/
+pub fn poll(window: &pancurses::Window) -> Option<Input> {
+    terminal::service_signals(window);
+
+    match window.getch()? {
+        Input::Character('q') | Input::Character(KEY_ESCAPE) => {
+            request_quit();
+            None
+        }
+        Input::KeyResize => {
+            terminal::update_screen_size(window);
+            Some(Input::KeyResize)
+        }
+        _ if ANY_KEY_QUITS.load(Ordering::Relaxed) => {
+            request_quit();
+            None
+        }
+        key => Some(key),
+    }
+}
+
+// Consumes all pending input, for effects that don't use the keyboard
+pub fn discard(window: &pancurses::Window) {
+    while poll(window).is_some() {}
+}
+
+// Blocks until a key is pressed or the demo is asked to quit otherwise
+pub fn wait_for_key(window: &pancurses::Window) {
+    while !quit_requested() {
+        match poll(window) {
+            Some(Input::KeyResize) | None => {
+                std::thread::sleep(std::time::Duration::from_millis(10))
+            }
+            Some(_) => break,
+        }
+    }
+}
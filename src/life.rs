@@ -0,0 +1,493 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::clock::{Clock, FixedTimestep, FrameLimiter};
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::field::Field;
+use crate::input;
+use crate::overlay;
+use crate::palette::put_glyph;
+use crate::rng::Rng;
+use crate::terminal;
+
+// How long the cellular automaton effect runs in seconds
+const LIFE_DURATION: f32 = 15.0;
+
+// Generations per second, independent of the frame rate
+const LIFE_STEP_SECONDS: f32 = 1.0 / 12.0;
+
+// Share of cells alive in a random starting soup
+const SOUP_DENSITY: f32 = 0.3;
+
+// Generations a cell has to live to be drawn with the last palette entry
+const AGE_SPAN: usize = 32;
+
+// Largest width and height of a loaded pattern
+const MAX_PATTERN_SIZE: usize = 4096;
+
+/*
+The rule a generation is computed by. Life-like rules give the neighbour counts
+a dead cell is born with and a live cell survives with, e.g. "B3/S23" for
+Conway's Life or "B36/S23" for HighLife, "23/3" is the same in the older S/B
+notation. Elementary rules ("W30", "W110") are one dimensional: every row is
+the next generation of the row above it, and the screen scrolls up.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    LifeLike {
+        birth: [bool; 9],
+        survival: [bool; 9],
+    },
+    Elementary(u8),
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        // B3/S23
+        let mut birth = [false; 9];
+        let mut survival = [false; 9];
+        birth[3] = true;
+        survival[2] = true;
+        survival[3] = true;
+
+        Rule::LifeLike { birth, survival }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = DemoError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || DemoError::Config(format!("invalid rule '{spec}'"));
+        let spec = spec.trim();
+
+        if let Some(number) = spec.strip_prefix(['W', 'w']) {
+            return number.parse().map(Rule::Elementary).map_err(|_| invalid());
+        }
+
+        let (first, second) = spec.split_once('/').ok_or_else(invalid)?;
+        let counts = |digits: &str| -> Result<[bool; 9], DemoError> {
+            let mut counts = [false; 9];
+            for digit in digits.chars() {
+                match digit.to_digit(10) {
+                    Some(count) if count <= 8 => counts[count as usize] = true,
+                    _ => return Err(invalid()),
+                }
+            }
+            Ok(counts)
+        };
+
+        let strip = |part: &str, prefix: char| {
+            part.strip_prefix([prefix, prefix.to_ascii_lowercase()])
+                .map(str::to_string)
+        };
+
+        let (birth, survival) = match (strip(first, 'B'), strip(second, 'S')) {
+            (Some(birth), Some(survival)) => (birth, survival),
+            _ => match (strip(first, 'S'), strip(second, 'B')) {
+                (Some(survival), Some(birth)) => (birth, survival),
+                // Without letters the survival counts come first
+                _ => (second.to_string(), first.to_string()),
+            },
+        };
+
+        Ok(Rule::LifeLike {
+            birth: counts(&birth)?,
+            survival: counts(&survival)?,
+        })
+    }
+}
+
+// Live cells of a pattern read from a run length encoded (RLE) file
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    width: usize,
+    height: usize,
+    cells: Vec<(usize, usize)>,
+    // The rule given in the header of the file, if any
+    pub rule: Option<Rule>,
+}
+
+impl Pattern {
+    pub fn load(path: &Path) -> Result<Pattern, DemoError> {
+        let source = std::fs::read_to_string(path).map_err(|error| DemoError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        Pattern::parse(&source)
+            .map_err(|error| DemoError::Config(format!("pattern {}: {error}", path.display())))
+    }
+
+    /*
+    Lines starting with '#' are comments, followed by a header like
+    "x = 3, y = 3, rule = B3/S23" and the cells: 'b' is dead, 'o' (or any
+    other letter) alive, '$' ends a row and '!' the pattern. Any of them can
+    be preceded by a repeat count. Live cells have to lie within the width
+    and height given by x and y.
+    */
+    fn parse(source: &str) -> Result<Pattern, DemoError> {
+        let mut pattern = Pattern {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+            rule: None,
+        };
+        let (mut width, mut height) = (MAX_PATTERN_SIZE, MAX_PATTERN_SIZE);
+        let (mut x, mut y) = (0_usize, 0_usize);
+        let mut count = 0_usize;
+
+        let too_large = || DemoError::Config("pattern runs beyond its size".to_string());
+
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('x') && line.contains('=') {
+                for entry in line.split(',') {
+                    let Some((key, value)) = entry.split_once('=') else {
+                        continue;
+                    };
+
+                    let size = || match value.trim().parse() {
+                        Ok(size) if size <= MAX_PATTERN_SIZE => Ok(size),
+                        _ => Err(DemoError::Config(format!(
+                            "invalid pattern size '{}', at most {MAX_PATTERN_SIZE}",
+                            value.trim()
+                        ))),
+                    };
+
+                    match key.trim() {
+                        "x" => width = size()?,
+                        "y" => height = size()?,
+                        "rule" => pattern.rule = Some(value.parse()?),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+
+            for symbol in line.chars() {
+                if let Some(digit) = symbol.to_digit(10) {
+                    count = count
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add(digit as usize))
+                        .ok_or_else(too_large)?;
+                    continue;
+                }
+
+                let run = count.max(1);
+                count = 0;
+
+                match symbol {
+                    'b' | '.' => x = x.checked_add(run).ok_or_else(too_large)?,
+                    '$' => {
+                        x = 0;
+                        y = y.checked_add(run).ok_or_else(too_large)?;
+                    }
+                    '!' => return pattern.finish(),
+                    symbol if symbol.is_ascii_alphabetic() => {
+                        let end = x.checked_add(run).filter(|&end| end <= width && y < height);
+                        let end = end.ok_or_else(too_large)?;
+
+                        pattern.cells.extend((x..end).map(|x| (x, y)));
+                        x = end;
+                    }
+                    symbol if symbol.is_whitespace() => {}
+                    symbol => {
+                        return Err(DemoError::Config(format!("unexpected '{symbol}'")));
+                    }
+                }
+            }
+        }
+
+        pattern.finish()
+    }
+
+    fn finish(mut self) -> Result<Pattern, DemoError> {
+        if self.cells.is_empty() {
+            return Err(DemoError::Config("no live cells".to_string()));
+        }
+
+        self.width = self.cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+        self.height = self.cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+        Ok(self)
+    }
+}
+
+/*
+Cells hold their age: 0 is dead, otherwise the number of generations the cell
+has been alive. The grid wraps around at the edges of the screen.
+*/
+struct Automaton {
+    ages: Field<u16>,
+    next: Field<u16>,
+    rule: Rule,
+    rng: Rng,
+}
+
+impl Automaton {
+    fn resize(&mut self, width: usize, height: usize, pattern: Option<&Pattern>) {
+        if (width, height) == (self.ages.width(), self.ages.height()) {
+            return;
+        }
+
+        self.ages.resize(width, height);
+        self.next.resize(width, height);
+        self.seed(pattern);
+    }
+
+    fn seed(&mut self, pattern: Option<&Pattern>) {
+        let (width, height) = (self.ages.width(), self.ages.height());
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        match (self.rule, pattern) {
+            // Patterns are centered, parts that don't fit wrap around
+            (_, Some(pattern)) => {
+                let center =
+                    |size: usize, extent: usize| (size / 2 + size - extent / 2 % size) % size;
+                let left = center(width, pattern.width);
+                let top = center(height, pattern.height);
+
+                for &(x, y) in &pattern.cells {
+                    match self.rule {
+                        // Only the first row of the pattern is used as the starting generation
+                        Rule::Elementary(_) if y == 0 => {
+                            self.ages.set((left + x) % width, height - 1, 1)
+                        }
+                        Rule::Elementary(_) => {}
+                        Rule::LifeLike { .. } => {
+                            self.ages.set((left + x) % width, (top + y) % height, 1)
+                        }
+                    }
+                }
+            }
+            // A single cell at the bottom grows the classic triangles
+            (Rule::Elementary(_), None) => self.ages.set(width / 2, height - 1, 1),
+            (Rule::LifeLike { .. }, None) => {
+                for y in 0..height {
+                    for x in 0..width {
+                        let alive = self.rng.next_f32() < SOUP_DENSITY;
+                        self.ages.set(x, y, alive as u16);
+                    }
+                }
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        let (width, height) = (self.ages.width(), self.ages.height());
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        match self.rule {
+            Rule::LifeLike { birth, survival } => {
+                for y in 0..height {
+                    for x in 0..width {
+                        let neighbours = self.neighbours(x, y);
+                        let age = *self.ages.get(x, y);
+
+                        let next = match age {
+                            0 if birth[neighbours] => 1,
+                            0 => 0,
+                            age if survival[neighbours] => age.saturating_add(1),
+                            _ => 0,
+                        };
+                        self.next.set(x, y, next);
+                    }
+                }
+            }
+            Rule::Elementary(rule) => {
+                // Everything scrolls up a row, the bottom row gets the new generation
+                for y in 0..height - 1 {
+                    for x in 0..width {
+                        self.next.set(x, y, *self.ages.get(x, y + 1));
+                    }
+                }
+
+                let last = height - 1;
+                for x in 0..width {
+                    let alive = |x: usize| (*self.ages.get(x % width, last) > 0) as u8;
+                    let pattern = alive(x + width - 1) << 2 | alive(x) << 1 | alive(x + 1);
+
+                    let next = match rule >> pattern & 1 {
+                        1 => self.ages.get(x, last).saturating_add(1),
+                        _ => 0,
+                    };
+                    self.next.set(x, last, next);
+                }
+            }
+        }
+
+        std::mem::swap(&mut self.ages, &mut self.next);
+    }
+
+    fn neighbours(&self, x: usize, y: usize) -> usize {
+        let (width, height) = (self.ages.width(), self.ages.height());
+        let mut count = 0;
+
+        for dy in [height - 1, 0, 1] {
+            for dx in [width - 1, 0, 1] {
+                if (dx, dy) != (0, 0) && *self.ages.get((x + dx) % width, (y + dy) % height) > 0 {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    fn population(&self) -> usize {
+        (0..self.ages.height())
+            .map(|y| self.ages.row(y).iter().filter(|&&age| age > 0).count())
+            .sum()
+    }
+
+    fn draw(&self, window: &pancurses::Window, config: &Config) {
+        let palette = &config.palette;
+
+        for y in 0..self.ages.height() {
+            for x in 0..self.ages.width() {
+                let age = *self.ages.get(x, y) as usize;
+                if age == 0 {
+                    window.mvaddch(y as i32, x as i32, ' ');
+                    continue;
+                }
+
+                // Newborn cells start at the second entry, as the first one is often blank
+                let first = (palette.len() > 1) as usize;
+                let index =
+                    (first + (age - 1) * (palette.len() - first) / AGE_SPAN).min(palette.len() - 1);
+                let (glyph, attributes) = palette.entry(index);
+
+                window.attrset(attributes);
+                put_glyph(window, y as i32, x as i32, glyph);
+            }
+        }
+
+        window.attrset(pancurses::A_NORMAL);
+    }
+}
+
+pub fn run_life_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut timestep = FixedTimestep::new(LIFE_STEP_SECONDS, clock.max_delta());
+
+    let mut automaton = Automaton {
+        ages: Field::new(0, 0),
+        next: Field::new(0, 0),
+        rule: config.life_rule,
+        rng: Rng::new(config.seed),
+    };
+
+    while clock.elapsed() < LIFE_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        automaton.resize(
+            screen_width as usize,
+            screen_height as usize,
+            config.life_pattern.as_ref(),
+        );
+
+        timestep.advance(clock.tick());
+        while timestep.next_step() {
+            automaton.step();
+
+            // Start over with a fresh soup once everything died out
+            if automaton.population() == 0 {
+                automaton.seed(None);
+            }
+        }
+
+        automaton.draw(window, config);
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(digits: &[usize]) -> [bool; 9] {
+        let mut counts = [false; 9];
+        for &digit in digits {
+            counts[digit] = true;
+        }
+        counts
+    }
+
+    fn error(source: &str) -> String {
+        Pattern::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rules_parse_in_every_notation() {
+        let high_life = Rule::LifeLike {
+            birth: counts(&[3, 6]),
+            survival: counts(&[2, 3]),
+        };
+
+        assert_eq!("B36/S23".parse::<Rule>().unwrap(), high_life);
+        assert_eq!("b36/s23".parse::<Rule>().unwrap(), high_life);
+        assert_eq!("S23/B36".parse::<Rule>().unwrap(), high_life);
+        assert_eq!("23/36".parse::<Rule>().unwrap(), high_life);
+        assert_eq!("B3/S23".parse::<Rule>().unwrap(), Rule::default());
+        assert_eq!("W30".parse::<Rule>().unwrap(), Rule::Elementary(30));
+        assert_eq!("w110".parse::<Rule>().unwrap(), Rule::Elementary(110));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        for spec in ["", "B3", "B9/S23", "B3/S2x", "W256", "W", "life"] {
+            assert!(spec.parse::<Rule>().is_err(), "{spec} was accepted");
+        }
+    }
+
+    #[test]
+    fn patterns_are_run_length_decoded() {
+        let glider =
+            Pattern::parse("#N Glider\nx = 3, y = 3, rule = B36/S23\nbob$2bo$3o!").unwrap();
+
+        assert_eq!(glider.cells, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!((glider.width, glider.height), (3, 3));
+        assert_eq!(glider.rule, Some("B36/S23".parse().unwrap()));
+
+        // Runs of rows leave empty rows between the cells
+        let rows = Pattern::parse("x = 12, y = 3\n12o2$o!").unwrap();
+        assert_eq!(rows.cells.len(), 13);
+        assert_eq!(rows.cells[12], (0, 2));
+        assert_eq!(rows.rule, None);
+    }
+
+    #[test]
+    fn patterns_stay_within_their_size() {
+        assert_eq!(error("x = 2, y = 1\n3o!"), "pattern runs beyond its size");
+        assert_eq!(error("x = 3, y = 1\no$o!"), "pattern runs beyond its size");
+        assert_eq!(
+            error("x = 1, y = 1\n99999999999999999999999o!"),
+            "pattern runs beyond its size"
+        );
+        assert_eq!(
+            error("x = 5000, y = 1\no!"),
+            "invalid pattern size '5000', at most 4096"
+        );
+        assert_eq!(error("x = 3, y = 3\n3b!"), "no live cells");
+        assert_eq!(error("x = 3, y = 3\no*o!"), "unexpected '*'");
+    }
+}
@@ -1,27 +1,133 @@
+mod clock;
+mod colors;
+mod config;
 mod cube3d;
+mod error;
+mod expr;
+mod field;
+mod figlet;
+mod fire;
+mod hershey;
+mod input;
+mod life;
+mod metaballs;
+mod overlay;
+mod palette;
 mod plasma;
+mod primitives;
+mod rain;
+mod raycaster;
+mod rng;
+mod rotozoom;
+mod starfield;
+mod terminal;
+mod texture;
+mod tunnel;
+mod voxel;
 
 // Default values for screen width & height
 pub static mut SCREEN_WIDTH: f32 = 80.0;
 pub static mut SCREEN_HEIGHT: f32 = 40.0;
 
+// Terminal cells are about twice as high as they are wide
+pub const CELL_ASPECT: f32 = 2.0;
+
+type EffectFn =
+    fn(&pancurses::Window, &mut clock::Clock, &config::Config) -> Result<(), error::DemoError>;
+
+// Effects that can be put into the sequence
+pub const EFFECTS: [(&str, EffectFn); 11] = [
+    ("cube", cube3d::run_cube_demo),
+    ("plasma", plasma::run_plasma_demo),
+    ("fire", fire::run_fire_demo),
+    ("starfield", starfield::run_starfield_demo),
+    ("tunnel", tunnel::run_tunnel_demo),
+    ("rotozoom", rotozoom::run_rotozoom_demo),
+    ("metaballs", metaballs::run_metaballs_demo),
+    ("rain", rain::run_rain_demo),
+    ("life", life::run_life_demo),
+    ("raycaster", raycaster::run_raycaster_demo),
+    ("voxel", voxel::run_voxel_demo),
+];
+
+// The sequence shown unless other effects are selected
+pub const DEFAULT_EFFECTS: [&str; 2] = ["cube", "plasma"];
+
+fn run_effect(
+    name: &str,
+    window: &pancurses::Window,
+    clock: &mut clock::Clock,
+    config: &config::Config,
+) -> Result<(), error::DemoError> {
+    match EFFECTS.iter().find(|(effect, _)| *effect == name) {
+        Some((_, run)) => {
+            window.erase();
+            run(window, clock, config)
+        }
+        None => Err(error::DemoError::Config(format!("unknown effect '{name}'"))),
+    }
+}
+
 fn main() {
-    let window = pancurses::initscr();
-
-    /*
-    Convert maximum screen height & width to f32
-    to be used later in the calculations for cube3d and plasma.
-    */
-    unsafe {
-        (SCREEN_HEIGHT, SCREEN_WIDTH) = {
-            let (y, x) = window.get_max_yx();
-            (y as f32, x as f32)
+    let config = match config::Config::from_args(std::env::args().skip(1)) {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            println!("{}", config::USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("ERROR: {error}\n\n{}", config::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if config.benchmark {
+        plasma::run_benchmark(&config);
+        return;
+    }
+
+    let window = terminal::init();
+    let result = run(&window, &config);
+    terminal::restore();
+
+    if let Err(error) = result {
+        eprintln!("ERROR: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run(window: &pancurses::Window, config: &config::Config) -> Result<(), error::DemoError> {
+    let mut clock = config.clock_mode.create_clock();
+    let mut rng = rng::Rng::new(config.seed);
+
+    input::set_any_key_quits(config.loop_mode);
+
+    terminal::update_screen_size(window);
+
+    // In loop mode every cycle runs with freshly randomized parameters
+    while !input::quit_requested() {
+        let cycle_config = match config.loop_mode {
+            true => config.randomized(&mut rng),
+            false => config.clone(),
         };
+
+        for effect in &cycle_config.effects {
+            if input::quit_requested() {
+                break;
+            }
+
+            run_effect(effect, window, &mut clock, &cycle_config)?;
+        }
+
+        if !config.loop_mode {
+            break;
+        }
     }
 
-    cube3d::run_cube_demo(&window);
-    plasma::run_plasma_demo(&window);
+    if !input::quit_requested() {
+        plasma::draw_outro(window, &config.font);
+        input::wait_for_key(window);
+    }
 
-    window.getch();
-    pancurses::endwin();
+    Ok(())
 }
@@ -0,0 +1,143 @@
+use crate::clock::{Clock, FrameLimiter};
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::field::Field;
+use crate::input;
+use crate::overlay;
+use crate::palette::put_glyph;
+use crate::rng::Rng;
+use crate::terminal;
+use crate::CELL_ASPECT;
+
+// How long the metaballs effect runs in seconds
+const METABALLS_DURATION: f32 = 10.0;
+
+const BALL_COUNT: usize = 5;
+
+// Field strength at which the surface of the blobs lies
+const THRESHOLD: f32 = 1.0;
+
+// How much stronger than the threshold the field has to get to reach the end of the palette
+const SHADE_RANGE: f32 = 4.0;
+
+/*
+Marching squares glyphs, indexed by which corners of a cell lie inside the
+surface: top left = 8, top right = 4, bottom right = 2, bottom left = 1. Cells
+with all corners inside (15) are shaded instead, the saddle cases 5 and 10 use
+the slope both of their corner cuts share.
+*/
+const EDGE_GLYPHS: [char; 15] = [
+    ' ', '\\', '/', '-', '\\', '\\', '|', '/', '/', '|', '/', '\\', '-', '/', '\\',
+];
+const ALL_CORNERS_INSIDE: usize = 15;
+
+// A charge moving on a Lissajous curve, positions are relative to the screen size
+struct Ball {
+    radius: f32,
+    frequency_x: f32,
+    frequency_y: f32,
+    phase_x: f32,
+    phase_y: f32,
+}
+
+impl Ball {
+    fn random(rng: &mut Rng) -> Ball {
+        Ball {
+            radius: rng.range(0.08, 0.16),
+            frequency_x: rng.range(0.3, 1.2),
+            frequency_y: rng.range(0.3, 1.2),
+            phase_x: rng.range(0.0, std::f32::consts::TAU),
+            phase_y: rng.range(0.0, std::f32::consts::TAU),
+        }
+    }
+
+    // Center in cells and radius in cells at time t
+    fn position(&self, t: f32, width: f32, height: f32) -> (f32, f32, f32) {
+        (
+            width * (0.5 + 0.35 * f32::sin(t * self.frequency_x + self.phase_x)),
+            height * (0.5 + 0.35 * f32::sin(t * self.frequency_y + self.phase_y)),
+            self.radius * width.min(height * CELL_ASPECT),
+        )
+    }
+}
+
+// Sums the charges of all balls at the corners of every cell
+fn sample_field(corners: &mut Field<f32>, balls: &[(f32, f32, f32)]) {
+    for y in 0..corners.height() {
+        for x in 0..corners.width() {
+            let strength = balls
+                .iter()
+                .map(|&(ball_x, ball_y, radius)| {
+                    let dx = x as f32 - ball_x;
+                    let dy = (y as f32 - ball_y) * CELL_ASPECT;
+                    radius * radius / (dx * dx + dy * dy).max(0.0001)
+                })
+                .sum();
+
+            corners.set(x, y, strength);
+        }
+    }
+}
+
+pub fn run_metaballs_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut rng = Rng::new(config.seed);
+
+    let balls: Vec<Ball> = (0..BALL_COUNT).map(|_| Ball::random(&mut rng)).collect();
+
+    // The field is sampled at cell corners, so it is one larger than the screen each way
+    let mut corners: Field<f32> = Field::new(0, 0);
+
+    while clock.elapsed() < METABALLS_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        corners.resize(screen_width as usize + 1, screen_height as usize + 1);
+
+        let t = clock.elapsed();
+        let positions: Vec<(f32, f32, f32)> = balls
+            .iter()
+            .map(|ball| ball.position(t, screen_width, screen_height))
+            .collect();
+
+        sample_field(&mut corners, &positions);
+
+        for y in 0..corners.height() - 1 {
+            for x in 0..corners.width() - 1 {
+                let inside = |x: usize, y: usize| (*corners.get(x, y) >= THRESHOLD) as usize;
+                let case = inside(x, y) << 3
+                    | inside(x + 1, y) << 2
+                    | inside(x + 1, y + 1) << 1
+                    | inside(x, y + 1);
+
+                let (glyph, attributes) = match case {
+                    ALL_CORNERS_INSIDE => {
+                        // Shade the inside with the plasma palette by the field strength
+                        let strength = (*corners.get(x, y) - THRESHOLD) / SHADE_RANGE;
+                        let index = ((strength * config.palette.len() as f32) as usize)
+                            .min(config.palette.len() - 1);
+                        config.palette.entry(index)
+                    }
+                    case => (EDGE_GLYPHS[case], pancurses::A_BOLD),
+                };
+
+                window.attrset(attributes);
+                put_glyph(window, y as i32, x as i32, glyph);
+            }
+        }
+        window.attrset(pancurses::A_NORMAL);
+
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+        clock.tick();
+    }
+
+    Ok(())
+}
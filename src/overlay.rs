@@ -0,0 +1,238 @@
+use std::str::FromStr;
+
+use pancurses::chtype;
+
+use crate::colors;
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::figlet::Font;
+use crate::palette::put_glyph;
+use crate::terminal;
+
+// Message shown when an overlay is enabled without giving a text
+const DEFAULT_TEXT: &str = "GREETINGS FROM RUST NCURSES 3D DEMO";
+
+// Scroller speed in cells per second at speed 1
+const SCROLL_SPEED: f32 = 12.0;
+
+// Columns cleared on both sides of a centered banner
+const BANNER_PADDING: i32 = 4;
+
+// Height of the scroller wave in rows, and how many characters one wave spans
+const WAVE_HEIGHT: f32 = 2.0;
+const WAVE_LENGTH: f32 = 24.0;
+
+// How a text moves over the effect below it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Motion {
+    // Runs across the screen from right to left, every character riding a sine wave
+    Scroll,
+    // Bounces around the screen like a logo
+    Bounce,
+    // Stays in the middle of the screen on a cleared box
+    Center,
+}
+
+impl FromStr for Motion {
+    type Err = DemoError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "scroll" => Ok(Motion::Scroll),
+            "bounce" => Ok(Motion::Bounce),
+            "center" => Ok(Motion::Center),
+            _ => Err(DemoError::Config(format!(
+                "unknown overlay motion '{name}'"
+            ))),
+        }
+    }
+}
+
+/*
+Text attributes and an optional color, e.g. "bold,blink,cyan", "big" draws
+the text as a banner in the FIGlet font. The color pair is only looked up
+while drawing, as colors are set up after the options are read.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    attributes: chtype,
+    color: Option<i16>,
+    big: bool,
+}
+
+impl Style {
+    pub fn new(attributes: chtype, big: bool) -> Style {
+        Style {
+            attributes,
+            color: None,
+            big,
+        }
+    }
+
+    fn attributes(&self) -> chtype {
+        match self.color {
+            Some(color) => self.attributes | colors::fg(color),
+            None => self.attributes,
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = DemoError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::default();
+
+        for name in spec.split(',').map(str::trim) {
+            match name {
+                "bold" => style.attributes |= pancurses::A_BOLD,
+                "dim" => style.attributes |= pancurses::A_DIM,
+                "blink" => style.attributes |= pancurses::A_BLINK,
+                "reverse" => style.attributes |= pancurses::A_REVERSE,
+                "underline" => style.attributes |= pancurses::A_UNDERLINE,
+                "big" => style.big = true,
+                "red" => style.color = Some(colors::RED),
+                "green" => style.color = Some(colors::GREEN),
+                "yellow" => style.color = Some(colors::YELLOW),
+                "blue" => style.color = Some(colors::BLUE),
+                "magenta" => style.color = Some(colors::MAGENTA),
+                "cyan" => style.color = Some(colors::CYAN),
+                "white" => style.color = Some(colors::WHITE),
+                _ => return Err(DemoError::Config(format!("unknown text style '{name}'"))),
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+// A line of text drawn on top of an effect
+#[derive(Clone, Debug)]
+pub struct Overlay {
+    pub text: String,
+    pub motion: Motion,
+    // Multiplier of how fast the text moves
+    pub speed: f32,
+    pub style: Style,
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Overlay {
+            text: DEFAULT_TEXT.to_string(),
+            motion: Motion::Scroll,
+            speed: 1.0,
+            style: Style::new(pancurses::A_BOLD, false),
+        }
+    }
+}
+
+impl Overlay {
+    // Draws the text at its position at time t, which is usually the effect's elapsed time
+    pub fn draw(&self, window: &pancurses::Window, font: &Font, t: f32) {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        // Banners too wide for the terminal fall back to plain text
+        let banner = match self.style.big {
+            true => Some(font.render(&self.text)).filter(|rows| {
+                rows.iter()
+                    .all(|row| row.chars().count() as f32 <= screen_width)
+            }),
+            false => None,
+        };
+        let rows: Vec<Vec<char>> = match &banner {
+            Some(rows) => rows.iter().map(|row| row.chars().collect()).collect(),
+            None => vec![self.text.chars().collect()],
+        };
+
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0) as f32;
+        let height = rows.len() as f32;
+        let t = t * self.speed;
+
+        // The blanks of banners are left out, so the effect shows between the letters
+        let transparent = banner.is_some();
+
+        window.attrset(self.style.attributes());
+
+        match self.motion {
+            Motion::Scroll => {
+                let left = screen_width - (t * SCROLL_SPEED).rem_euclid(screen_width + width);
+                let baseline = screen_height * 0.75 - height / 2.0;
+
+                for column in 0..width as usize {
+                    let x = (left + column as f32).round();
+                    if x < 0.0 || x >= screen_width {
+                        continue;
+                    }
+
+                    let phase = x / WAVE_LENGTH * std::f32::consts::TAU + t * 3.0;
+                    let top = (baseline + WAVE_HEIGHT * f32::sin(phase)).round() as i32;
+
+                    for (y, row) in rows.iter().enumerate() {
+                        match row.get(column) {
+                            Some(' ') if transparent => {}
+                            Some(&glyph) => put_glyph(window, top + y as i32, x as i32, glyph),
+                            None => {}
+                        }
+                    }
+                }
+            }
+            Motion::Bounce => {
+                let x = (0.5 + 0.5 * f32::sin(t * 5.0)) * (screen_width - width).max(0.0);
+                let y = f32::sin(t).abs() * (screen_height - height).max(0.0);
+                draw_rows(
+                    window,
+                    &rows,
+                    y.round() as i32,
+                    x.round() as i32,
+                    transparent,
+                );
+            }
+            Motion::Center => {
+                let (center_x, center_y) = (screen_width as i32 / 2, screen_height as i32 / 2);
+                let (width, height) = (width as i32, height as i32);
+                let top = center_y - height / 2;
+
+                // Clear a box around the text, so it stays readable on busy effects. Plain
+                // text gets a box twice its width, banners a few columns on each side.
+                let padding = match banner {
+                    Some(_) => BANNER_PADDING,
+                    None => width / 2,
+                };
+                let left = center_x - width / 2;
+                for y in top - 1..top + height + 1 {
+                    for x in left - padding..left + width + padding {
+                        window.mvaddch(y, x, ' ');
+                    }
+                }
+
+                draw_rows(window, &rows, top, left, false);
+            }
+        }
+
+        window.attrset(pancurses::A_NORMAL);
+    }
+}
+
+fn draw_rows(
+    window: &pancurses::Window,
+    rows: &[Vec<char>],
+    top: i32,
+    left: i32,
+    transparent: bool,
+) {
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &glyph) in row.iter().enumerate() {
+            if glyph != ' ' || !transparent {
+                put_glyph(window, top + y as i32, left + x as i32, glyph);
+            }
+        }
+    }
+}
+
+// Draws the overlay set up on the command line, if any, on top of the current frame
+pub fn draw_configured(window: &pancurses::Window, config: &Config, t: f32) {
+    if let Some(overlay) = &config.overlay {
+        overlay.draw(window, &config.font, t);
+    }
+}
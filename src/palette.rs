@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use pancurses::chtype;
+
+use crate::colors;
+use crate::error::DemoError;
+
+// Field values covering the whole palette once, as in the original plasma
+pub const PALETTE_SPAN: f32 = 256.0;
+
+// The palette the plasma was drawn with originally
+const CLASSIC: &str = ".,;'\"<>/(){}&%#@";
+
+// Named character ramps, ordered from sparse to dense
+const NAMED_GLYPHS: [(&str, &str); 4] = [
+    ("classic", CLASSIC),
+    ("ascii", " .:-=+*#%@"),
+    ("blocks", " ░▒▓█"),
+    ("braille", "⠀⠁⠃⠇⡇⡏⡟⡿⣿"),
+];
+
+// Named color gradients, each color covering an equal share of the palette
+const NAMED_GRADIENTS: [(&str, &[i16]); 5] = [
+    ("none", &[]),
+    ("fire", &[colors::RED, colors::YELLOW, colors::WHITE]),
+    ("ocean", &[colors::BLUE, colors::CYAN, colors::WHITE]),
+    ("toxic", &[colors::GREEN, colors::YELLOW]),
+    (
+        "rainbow",
+        &[
+            colors::RED,
+            colors::YELLOW,
+            colors::GREEN,
+            colors::CYAN,
+            colors::BLUE,
+            colors::MAGENTA,
+        ],
+    ),
+];
+
+// How the palette moves over time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CycleMode {
+    // Bands scroll smoothly through the field and wrap around
+    Linear,
+    // Runs up the palette and back down again, without a hard wrap
+    PingPong,
+    // Bands stay in place while their glyphs change one palette entry at a time
+    Stepped,
+}
+
+impl FromStr for CycleMode {
+    type Err = DemoError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "linear" => Ok(CycleMode::Linear),
+            "pingpong" => Ok(CycleMode::PingPong),
+            "stepped" => Ok(CycleMode::Stepped),
+            _ => Err(DemoError::Config(format!("unknown cycle mode '{name}'"))),
+        }
+    }
+}
+
+// Characters (and optionally colors) field values are mapped to
+#[derive(Clone)]
+pub struct Palette {
+    glyphs: Vec<char>,
+    colors: Vec<i16>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            glyphs: CLASSIC.chars().collect(),
+            colors: Vec::new(),
+        }
+    }
+}
+
+impl Palette {
+    // Accepts a palette name or "custom:<characters>"
+    pub fn set_glyphs(&mut self, spec: &str) -> Result<(), DemoError> {
+        let glyphs = match spec.strip_prefix("custom:") {
+            Some(custom) => custom,
+            None => NAMED_GLYPHS
+                .iter()
+                .find(|(name, _)| *name == spec)
+                .map(|(_, glyphs)| *glyphs)
+                .ok_or_else(|| DemoError::Config(format!("unknown palette '{spec}'")))?,
+        };
+
+        if glyphs.is_empty() {
+            return Err(DemoError::Config("palette is empty".to_string()));
+        }
+
+        self.glyphs = glyphs.chars().collect();
+        Ok(())
+    }
+
+    pub fn set_colors(&mut self, name: &str) -> Result<(), DemoError> {
+        self.colors = NAMED_GRADIENTS
+            .iter()
+            .find(|(gradient, _)| *gradient == name)
+            .map(|(_, colors)| colors.to_vec())
+            .ok_or_else(|| DemoError::Config(format!("unknown colors '{name}'")))?;
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    // Index of the palette entry a field value falls into, after shifting it by the cycle offset
+    pub fn index(&self, value: f32, offset: f32, mode: CycleMode) -> usize {
+        let len = self.len() as f32;
+        let band = PALETTE_SPAN / len;
+
+        match mode {
+            CycleMode::Linear => {
+                let position = (value.round() + offset.trunc()).rem_euclid(PALETTE_SPAN);
+                (position / band) as usize % self.len()
+            }
+            CycleMode::PingPong => {
+                let mut position = (value.round() + offset.trunc()).rem_euclid(2.0 * PALETTE_SPAN);
+                if position >= PALETTE_SPAN {
+                    position = 2.0 * PALETTE_SPAN - position;
+                }
+                ((position / band) as usize).min(self.len() - 1)
+            }
+            CycleMode::Stepped => {
+                let position = value.round().rem_euclid(PALETTE_SPAN);
+                ((position / band) as usize + (offset / band) as usize) % self.len()
+            }
+        }
+    }
+
+    // Glyph and color attribute of a palette entry
+    pub fn entry(&self, index: usize) -> (char, chtype) {
+        let attributes = match self.colors.len() {
+            0 => 0,
+            count => colors::fg(self.colors[index * count / self.len()]),
+        };
+
+        (self.glyphs[index], attributes)
+    }
+
+    pub fn lookup(&self, value: f32, offset: f32, mode: CycleMode) -> (char, chtype) {
+        self.entry(self.index(value, offset, mode))
+    }
+}
+
+/*
+Draws a single character. pancurses converts chars to chtype by casting, which
+only works for ASCII, everything else has to go through the string functions.
+*/
+pub fn put_glyph(window: &pancurses::Window, y: i32, x: i32, glyph: char) {
+    if glyph.is_ascii() {
+        window.mvaddch(y, x, glyph);
+    } else {
+        window.mvaddstr(y, x, glyph.encode_utf8(&mut [0; 4]));
+    }
+}
@@ -1,68 +1,182 @@
-const PALETTE: [char; 16] = [
-    '.', ',', ';', '\'', '"', '<', '>', '/', '(', ')', '{', '}', '&', '%', '#', '@',
-];
+use crate::clock::{Clock, FrameLimiter};
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::expr::{Expr, Vars};
+use crate::field::Field;
+use crate::figlet::Font;
+use crate::input;
+use crate::overlay::{self, Motion, Overlay, Style};
+use crate::palette::put_glyph;
+use crate::terminal;
 
-// Plasma effect function used and adapted from https://rosettacode.org/wiki/Plasma_effect
-fn generate_plasma(plasma_vec: &mut [f32], screen_height: f32, screen_width: f32, t: f32) {
-    if plasma_vec.len() < (screen_height * screen_height) as usize {
-        panic!("Plasma vector has incorrect size!");
+// How long the plasma effect runs in seconds
+const PLASMA_DURATION: f32 = 10.0;
+
+// Entries of the sine lookup table covering one period, a power of two so
+// angles can be wrapped with a mask
+const SINE_TABLE_SIZE: usize = 8192;
+
+struct SineTable {
+    values: Vec<f32>,
+}
+
+impl SineTable {
+    fn new() -> SineTable {
+        SineTable {
+            values: (0..SINE_TABLE_SIZE)
+                .map(|i| f32::sin(i as f32 / SINE_TABLE_SIZE as f32 * std::f32::consts::TAU))
+                .collect(),
+        }
+    }
+
+    fn sin(&self, angle: f32) -> f32 {
+        let index = (angle * (SINE_TABLE_SIZE as f32 / std::f32::consts::TAU)).round() as i64;
+        self.values[(index & (SINE_TABLE_SIZE as i64 - 1)) as usize]
+    }
+}
+
+/*
+The radial terms of the plasma only depend on the cell position, so they are
+computed once per viewport size instead of every frame: the wave around the
+center entirely, the distance from the origin up to the time dependent phase.
+*/
+struct PlasmaTables {
+    center_wave: Field<f32>,
+    origin_distance: Field<f32>,
+    sine: SineTable,
+}
+
+impl PlasmaTables {
+    fn new() -> PlasmaTables {
+        PlasmaTables {
+            center_wave: Field::new(0, 0),
+            origin_distance: Field::new(0, 0),
+            sine: SineTable::new(),
+        }
     }
 
-    for y in 0..screen_height as i32 {
-        for x in 0..screen_width as i32 {
-            plasma_vec[(y * screen_height as i32 + x) as usize] = (128.0
-                + (128.0 * f32::sin((x as f32 / 8.0) - f32::cos(t / 2.0)))
+    // Rebuilds the tables if the viewport size changed
+    fn resize(&mut self, width: usize, height: usize, threads: usize) {
+        if self.center_wave.width() == width && self.center_wave.height() == height {
+            return;
+        }
+
+        let (widthf, heightf) = (width as f32, height as f32);
+
+        self.center_wave.resize(width, height);
+        self.center_wave.fill_rows(threads, |y, row| {
+            let yf = y as f32;
+
+            for (x, cell) in row.iter_mut().enumerate() {
+                let xf = x as f32;
+
+                *cell = 128.0
+                    * f32::sin(
+                        f32::sqrt(
+                            (xf - widthf / 2.0) * (xf - widthf / 2.0)
+                                + (yf - heightf / 2.0) * (yf - heightf / 2.0),
+                        ) / 4.0,
+                    );
+            }
+        });
+
+        self.origin_distance.resize(width, height);
+        self.origin_distance.fill_rows(threads, |y, row| {
+            let yf = y as f32;
+
+            for (x, cell) in row.iter_mut().enumerate() {
+                let xf = x as f32;
+                *cell = f32::sqrt(xf * xf + yf * yf) / 4.0;
+            }
+        });
+    }
+}
+
+// Same field as generate_plasma_direct, assembled from the lookup tables
+fn generate_plasma(plasma: &mut Field<f32>, tables: &PlasmaTables, t: f32, threads: usize) {
+    let sine = &tables.sine;
+
+    // The horizontal wave only depends on x, so it is shared by all rows
+    let column_phase = f32::cos(t / 2.0);
+    let column_wave: Vec<f32> = (0..plasma.width())
+        .map(|x| 128.0 * sine.sin((x as f32 / 8.0) - column_phase))
+        .collect();
+
+    let row_phase = f32::sin(t) * 2.0;
+    let origin_phase = f32::sin(t / 4.0);
+
+    plasma.fill_rows(threads, |y, row| {
+        let row_wave = 128.0 * sine.sin((y as f32 / 16.0) - row_phase);
+        let center_wave = tables.center_wave.row(y);
+        let origin_distance = tables.origin_distance.row(y);
+
+        for (x, cell) in row.iter_mut().enumerate() {
+            *cell = (4.0 * 128.0
+                + column_wave[x]
+                + row_wave
+                + center_wave[x]
+                + 128.0 * sine.sin(origin_distance[x] - origin_phase))
+                / 4.0;
+        }
+    });
+}
+
+// Plasma effect function used and adapted from https://rosettacode.org/wiki/Plasma_effect
+// Computes every term per cell, kept as the reference for the lookup table version.
+fn generate_plasma_direct(plasma: &mut Field<f32>, t: f32, threads: usize) {
+    let width = plasma.width() as f32;
+    let height = plasma.height() as f32;
+
+    plasma.fill_rows(threads, |y, row| {
+        let yf = y as f32;
+
+        for (x, cell) in row.iter_mut().enumerate() {
+            let xf = x as f32;
+
+            *cell = (128.0
+                + (128.0 * f32::sin((xf / 8.0) - f32::cos(t / 2.0)))
                 + 128.0
-                + (128.0 * f32::sin((y as f32 / 16.0) - f32::sin(t) * 2.0))
+                + (128.0 * f32::sin((yf / 16.0) - f32::sin(t) * 2.0))
                 + 128.0
                 + (128.0
                     * f32::sin(
                         f32::sqrt(
-                            (x as f32 - screen_width / 2.0) * (x as f32 - screen_width / 2.0)
-                                + (y as f32 - screen_height / 2.0)
-                                    * (y as f32 - screen_height / 2.0),
+                            (xf - width / 2.0) * (xf - width / 2.0)
+                                + (yf - height / 2.0) * (yf - height / 2.0),
                         ) / 4.0,
                     ))
                 + 128.0
-                + (128.0
-                    * f32::sin(
-                        (f32::sqrt(x as f32 * x as f32 + y as f32 * y as f32) / 4.0)
-                            - f32::sin(t / 4.0),
-                    )))
+                + (128.0 * f32::sin((f32::sqrt(xf * xf + yf * yf) / 4.0) - f32::sin(t / 4.0))))
                 / 4.0;
         }
-    }
+    });
 }
 
-fn draw_text(window: &pancurses::Window, screen_height: f32, screen_width: f32) {
-    let msg = "H Y P N O T I Z E";
+// Fills the field with a user supplied formula instead of the built-in one
+fn generate_plasma_formula(plasma: &mut Field<f32>, formula: &Expr, t: f32, threads: usize) {
+    let width = plasma.width() as f32;
+    let height = plasma.height() as f32;
 
-    window.attron(pancurses::A_BLINK | pancurses::A_BOLD);
-    for y in (screen_height as i32 / 2 - 1)..(screen_height as i32 / 2) + 2 {
-        for x in (screen_width as i32 / 2 - (msg.len()) as i32)
-            ..(screen_width as i32 / 2 + msg.len() as i32)
-        {
-            window.mvaddch(y, x, ' ');
-        }
-    }
+    plasma.fill_rows(threads, |y, row| {
+        let mut stack = Vec::new();
+        let mut vars = Vars {
+            y: y as f32,
+            t,
+            w: width,
+            h: height,
+            ..Vars::default()
+        };
 
-    window.mvaddstr(
-        screen_height as i32 / 2,
-        screen_width as i32 / 2 - (msg.len() as i32 / 2),
-        msg,
-    );
-    window.mv(screen_height as i32 - 1, screen_width as i32 - 1);
-    window.attroff(pancurses::A_BLINK | pancurses::A_BOLD);
+        for (x, cell) in row.iter_mut().enumerate() {
+            vars.x = x as f32;
+            *cell = formula.eval(&vars, &mut stack);
+        }
+    });
 }
 
-fn draw_outro(window: &pancurses::Window, screen_height: f32) {
+pub fn draw_outro(window: &pancurses::Window, font: &Font) {
     // https://ascii-art.net/about.php
-    let text = "
-CUBE3D AND PLASMA DEMO
-
-2024 T
-
--------------------------------------------------
+    let art = "-------------------------------------------------
 00000000: 2020 2020 2020 2020 2020 2020 2020 5f5f
 00000010: 205f 5f0a 2020 2020 2020 2020 2020 2020
 00000020: 2c3b 3a3a 5c3a 3a5c 0a20 2020 2020 2020
@@ -98,9 +212,33 @@ CUBE3D AND PLASMA DEMO
 00000200: 210a
 ";
 
+    let (screen_width, screen_height) = terminal::screen_size();
+
+    // The titles are typed out as banners if they fit on the screen
+    let mut text = String::from("\n");
+    for title in ["CUBE3D AND PLASMA DEMO", "2024 T"] {
+        let banner = font.render(title);
+        match banner
+            .iter()
+            .all(|row| (row.chars().count() as f32) < screen_width)
+        {
+            true => banner
+                .iter()
+                .for_each(|row| text += &format!("{}\n", row.trim_end())),
+            false => text += &format!("{title}\n"),
+        }
+        text.push('\n');
+    }
+    text += art;
+
     window.scrollok(true);
     window.mv(screen_height as i32 - 1, 0);
     for ch in text.chars() {
+        input::discard(window);
+        if input::quit_requested() {
+            break;
+        }
+
         window.addch(ch);
         std::thread::sleep(std::time::Duration::from_millis(20));
         window.refresh();
@@ -108,53 +246,223 @@ CUBE3D AND PLASMA DEMO
     window.scrollok(false);
 }
 
-pub fn run_plasma_demo(window: &pancurses::Window) {
-    // Use t as timer for determining when to stop the demo.
-    let mut t: f32 = 0.0;
+pub fn run_plasma_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    // The clock both drives the plasma animation and determines when to stop
+    // the demo.
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
 
-    // Use time now to determine start_time and current time to calculate
-    // plasma for the simulation.
-    let time_now = || -> std::time::Duration {
-        std::time::SystemTime::now()
-            .duration_since(std::time::SystemTime::UNIX_EPOCH)
-            .unwrap()
+    // This field contains the 2D plasma information for each cell
+    let mut plasma: Field<f32> = Field::new(0, 0);
+    let mut tables = PlasmaTables::new();
+    let title = Overlay {
+        text: "H Y P N O T I Z E".to_string(),
+        motion: Motion::Center,
+        speed: 1.0,
+        style: Style::new(pancurses::A_BLINK | pancurses::A_BOLD, true),
     };
 
-    let start_time = time_now();
+    while clock.elapsed() < PLASMA_DURATION && !input::quit_requested() {
+        let now = clock.elapsed() * config.plasma_speed;
+
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        // Follow the terminal size in case it was resized
+        plasma.resize(screen_width as usize, screen_height as usize);
+
+        match &config.plasma_formula {
+            Some(formula) => generate_plasma_formula(&mut plasma, formula, now, config.threads),
+            None => {
+                tables.resize(plasma.width(), plasma.height(), config.threads);
+                generate_plasma(&mut plasma, &tables, now, config.threads);
+            }
+        }
+
+        let cycle_offset = clock.elapsed() * config.plasma_cycle_speed;
 
-    let screen_width: f32;
-    let screen_height: f32;
-    unsafe {
-        screen_width = crate::SCREEN_WIDTH;
-        screen_height = crate::SCREEN_HEIGHT;
+        for y in 0..plasma.height() {
+            for x in 0..plasma.width() {
+                let (glyph, attributes) =
+                    config
+                        .palette
+                        .lookup(*plasma.get(x, y), cycle_offset, config.cycle_mode);
+
+                window.attrset(attributes);
+                put_glyph(window, y as i32, x as i32, glyph);
+            }
+        }
+        window.attrset(pancurses::A_NORMAL);
+
+        title.draw(window, &config.font, clock.elapsed());
+        overlay::draw_configured(window, config, clock.elapsed());
+
+        // Move cursor out of the way
+        window.mv(screen_height as i32 - 1, screen_width as i32 - 1);
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+        clock.tick();
     }
 
-    // This vector contains the 2D plasma inforamtion for each cell
-    let mut plasma: Vec<f32> = vec![0.0; (screen_height * screen_width) as usize];
+    Ok(())
+}
+
+/*
+Prints how many plasma cells per second are generated for a few terminal sizes
+and thread counts. Runs without curses, started with --bench.
+*/
+pub fn run_benchmark(config: &Config) {
+    const SIZES: [(usize, usize); 4] = [(80, 24), (200, 60), (400, 120), (800, 240)];
+    const FRAMES: usize = 50;
 
-    while t < 10.0 {
-        let now = (time_now() - start_time).as_secs_f32();
+    let mut thread_counts = vec![1];
+    while thread_counts[thread_counts.len() - 1] * 2 <= config.threads {
+        thread_counts.push(thread_counts[thread_counts.len() - 1] * 2);
+    }
+    if thread_counts[thread_counts.len() - 1] != config.threads {
+        thread_counts.push(config.threads);
+    }
 
-        generate_plasma(&mut plasma, screen_height, screen_width, now);
+    let methods = match config.plasma_formula {
+        Some(_) => vec!["formula"],
+        None => vec!["direct", "lookup"],
+    };
 
-        for y in 0..screen_height as i32 {
-            for x in 0..screen_width as i32 {
-                window.mvaddch(
-                    y,
-                    x,
-                    PALETTE[((plasma[(y * screen_height as i32 + x) as usize].round() as i32
-                        + ((now * 100.0) as i32))
-                        / PALETTE.len() as i32
-                        % PALETTE.len() as i32) as usize],
+    println!(
+        "{:>10} {:>8} {:>8} {:>14} {:>10}",
+        "size", "method", "threads", "Mcells/s", "ms/frame"
+    );
+
+    for (width, height) in SIZES {
+        let mut plasma: Field<f32> = Field::new(width, height);
+        let mut tables = PlasmaTables::new();
+        tables.resize(width, height, config.threads);
+
+        for method in &methods {
+            // Small sizes run on fewer threads than asked for, each count is measured once
+            let mut measured = Vec::new();
+
+            for &threads in &thread_counts {
+                let threads = plasma.fill_threads(threads);
+                if measured.contains(&threads) {
+                    continue;
+                }
+                measured.push(threads);
+
+                let start = std::time::Instant::now();
+
+                for frame in 0..FRAMES {
+                    let t = frame as f32 / 60.0;
+
+                    match (&config.plasma_formula, *method) {
+                        (Some(formula), _) => {
+                            generate_plasma_formula(&mut plasma, formula, t, threads)
+                        }
+                        (None, "direct") => generate_plasma_direct(&mut plasma, t, threads),
+                        (None, _) => generate_plasma(&mut plasma, &tables, t, threads),
+                    }
+                }
+
+                let seconds = start.elapsed().as_secs_f32();
+                println!(
+                    "{:>10} {:>8} {:>8} {:>14.2} {:>10.3}",
+                    format!("{width}x{height}"),
+                    method,
+                    threads,
+                    (width * height * FRAMES) as f32 / seconds / 1_000_000.0,
+                    seconds * 1000.0 / FRAMES as f32
                 );
             }
         }
+    }
+}
 
-        draw_text(window, screen_height, screen_width);
-        window.refresh();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The built-in plasma written as a --formula
+    const BUILTIN_FORMULA: &str = "(128 + 128 * sin(x / 8 - cos(t / 2)) \
+        + 128 + 128 * sin(y / 16 - sin(t) * 2) \
+        + 128 + 128 * sin(sqrt((x - w / 2) * (x - w / 2) + (y - h / 2) * (y - h / 2)) / 4) \
+        + 128 + 128 * sin(sqrt(x * x + y * y) / 4 - sin(t / 4))) / 4";
+
+    #[test]
+    fn formula_matches_builtin_plasma() {
+        let formula = Expr::compile(BUILTIN_FORMULA).unwrap();
+        let mut direct: Field<f32> = Field::new(80, 24);
+        let mut evaluated: Field<f32> = Field::new(80, 24);
+
+        for frame in 0..20 {
+            let t = frame as f32 * 0.37;
+            generate_plasma_direct(&mut direct, t, 1);
+            generate_plasma_formula(&mut evaluated, &formula, t, 1);
 
-        t += 0.001;
+            for y in 0..24 {
+                for x in 0..80 {
+                    let (expected, actual) = (*direct.get(x, y), *evaluated.get(x, y));
+                    assert!(
+                        (expected - actual).abs() < 1e-3,
+                        "({x}, {y}) at t = {t}: {actual} instead of {expected}"
+                    );
+                }
+            }
+        }
     }
 
-    draw_outro(window, screen_height);
+    // Compares the palette entries both plasma versions pick over a few seconds of animation
+    #[test]
+    fn lookup_tables_draw_like_direct_computation() {
+        const SECONDS: usize = 10;
+        const FRAMES_PER_SECOND: usize = 60;
+
+        let config = Config::default();
+        let (width, height) = (200, 60);
+        let mut direct: Field<f32> = Field::new(width, height);
+        let mut lookup: Field<f32> = Field::new(width, height);
+        let mut tables = PlasmaTables::new();
+        tables.resize(width, height, 1);
+
+        let mut differing_cells = 0;
+        let mut max_error: f32 = 0.0;
+
+        for frame in 0..SECONDS * FRAMES_PER_SECOND {
+            let t = frame as f32 / FRAMES_PER_SECOND as f32;
+            let cycle_offset = t * config.plasma_cycle_speed;
+
+            generate_plasma_direct(&mut direct, t, 1);
+            generate_plasma(&mut lookup, &tables, t, 1);
+
+            for y in 0..height {
+                for x in 0..width {
+                    let (direct_value, lookup_value) = (*direct.get(x, y), *lookup.get(x, y));
+                    max_error = max_error.max((direct_value - lookup_value).abs());
+
+                    if config
+                        .palette
+                        .index(direct_value, cycle_offset, config.cycle_mode)
+                        != config
+                            .palette
+                            .index(lookup_value, cycle_offset, config.cycle_mode)
+                    {
+                        differing_cells += 1;
+                    }
+                }
+            }
+        }
+
+        // Values only differ where the sine table rounds the angle, which can
+        // tip a cell over to the neighbouring palette entry
+        let total_cells = width * height * SECONDS * FRAMES_PER_SECOND;
+        assert!(max_error < 0.05, "lookup values are up to {max_error} off");
+        assert!(
+            differing_cells * 1000 < total_cells,
+            "{differing_cells} of {total_cells} cells drawn differently"
+        );
+    }
 }
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+use std::str::FromStr;
+
+use crate::cube3d::{create_cube, Mesh, Vec3, Vertex};
+use crate::error::DemoError;
+
+// Segments around the round shapes and rings from pole to pole of the UV sphere
+const SLICES: usize = 16;
+const STACKS: usize = 8;
+
+// Radius of the tube of the torus, relative to the radius of its ring
+const TORUS_TUBE: f32 = 0.4;
+
+// Cells along each side of the plane grid
+const PLANE_DIVISIONS: usize = 8;
+
+// Subdivision levels of the icosphere, every level has four times the triangles
+const DEFAULT_ICOSPHERE_LEVEL: u32 = 1;
+const MAX_ICOSPHERE_LEVEL: u32 = 4;
+
+/*
+The mesh spinning in the cube effect. Every shape fits the same box from -1 to
+1 as the cube, its triangles wound counterclockwise as seen from outside, so
+their normals point away from the shape.
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Cube,
+    Sphere,
+    // Subdivided icosahedron, the level is how often every triangle is split in four
+    Icosphere(u32),
+    Torus,
+    Cylinder,
+    Cone,
+    Plane,
+}
+
+impl FromStr for Shape {
+    type Err = DemoError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, level) = match spec.split_once(':') {
+            Some((name, level)) => (name, Some(level)),
+            None => (spec, None),
+        };
+
+        match (name, level) {
+            ("cube", None) => Ok(Shape::Cube),
+            ("sphere", None) => Ok(Shape::Sphere),
+            ("icosphere", None) => Ok(Shape::Icosphere(DEFAULT_ICOSPHERE_LEVEL)),
+            ("icosphere", Some(level)) => match level.parse() {
+                Ok(level) if level <= MAX_ICOSPHERE_LEVEL => Ok(Shape::Icosphere(level)),
+                _ => Err(DemoError::Config(format!(
+                    "icosphere level '{level}' is not a number from 0 to {MAX_ICOSPHERE_LEVEL}"
+                ))),
+            },
+            ("torus", None) => Ok(Shape::Torus),
+            ("cylinder", None) => Ok(Shape::Cylinder),
+            ("cone", None) => Ok(Shape::Cone),
+            ("plane", None) => Ok(Shape::Plane),
+            _ => Err(DemoError::Config(format!("unknown shape '{spec}'"))),
+        }
+    }
+}
+
+impl Shape {
+    pub(crate) fn mesh(&self) -> Result<Mesh, DemoError> {
+        match *self {
+            Shape::Cube => Ok(create_cube()),
+            Shape::Sphere => uv_sphere(1.0, STACKS, SLICES),
+            Shape::Icosphere(level) => icosphere(1.0, level),
+            Shape::Torus => torus(1.0 - TORUS_TUBE, TORUS_TUBE, SLICES, SLICES / 2),
+            Shape::Cylinder => cylinder(1.0, 2.0, SLICES),
+            Shape::Cone => cone(1.0, 2.0, SLICES),
+            Shape::Plane => plane(2.0, PLANE_DIVISIONS),
+        }
+    }
+}
+
+fn vertex(x: f32, y: f32, z: f32) -> Vertex {
+    Vertex {
+        position: Vec3 { x, y, z },
+    }
+}
+
+// Two triangles of a quad, its corners given counterclockwise as seen from outside
+fn quad(vertices: &mut Vec<Vertex>, corners: [&Vertex; 4]) {
+    let [a, b, c, d] = corners;
+    vertices.extend([a, b, c, a, c, d].map(Vertex::clone));
+}
+
+// Latitude and longitude grid, the rings touching the poles are triangles
+pub(crate) fn uv_sphere(radius: f32, stacks: usize, slices: usize) -> Result<Mesh, DemoError> {
+    let point = |stack: usize, slice: usize| {
+        let (polar, azimuth) = (
+            PI * stack as f32 / stacks as f32,
+            TAU * slice as f32 / slices as f32,
+        );
+        vertex(
+            radius * polar.sin() * azimuth.cos(),
+            radius * polar.cos(),
+            radius * polar.sin() * azimuth.sin(),
+        )
+    };
+
+    let mut vertices = Vec::new();
+    for stack in 0..stacks {
+        for slice in 0..slices {
+            let (a, b, c, d) = (
+                point(stack, slice),
+                point(stack, slice + 1),
+                point(stack + 1, slice + 1),
+                point(stack + 1, slice),
+            );
+
+            match stack {
+                0 => vertices.extend([a, c, d]),
+                stack if stack == stacks - 1 => vertices.extend([a, b, c]),
+                _ => quad(&mut vertices, [&a, &b, &c, &d]),
+            }
+        }
+    }
+
+    Mesh::from_vertices(vertices)
+}
+
+/*
+An icosahedron whose triangles are split into four at the midpoints of their
+edges level times, the new corners pushed out onto the sphere. Corners are
+shared between triangles while subdividing, so every edge is split only once.
+*/
+pub(crate) fn icosphere(radius: f32, level: u32) -> Result<Mesh, DemoError> {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut corners: Vec<[f32; 3]> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .iter()
+    .map(normalize)
+    .collect();
+
+    let mut faces: Vec<[usize; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..level {
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize| -> usize {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (a, b) = (corners[a], corners[b]);
+                corners.push(normalize(&[
+                    (a[0] + b[0]) / 2.0,
+                    (a[1] + b[1]) / 2.0,
+                    (a[2] + b[2]) / 2.0,
+                ]));
+                corners.len() - 1
+            })
+        };
+
+        faces = faces
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    Mesh::from_vertices(
+        faces
+            .iter()
+            .flatten()
+            .map(|&corner| {
+                let [x, y, z] = corners[corner];
+                vertex(x * radius, y * radius, z * radius)
+            })
+            .collect(),
+    )
+}
+
+fn normalize(point: &[f32; 3]) -> [f32; 3] {
+    let length = (point[0] * point[0] + point[1] * point[1] + point[2] * point[2]).sqrt();
+    point.map(|coordinate| coordinate / length)
+}
+
+// A ring of the given radius around the y axis, with a tube of the given radius around it
+pub(crate) fn torus(
+    radius: f32,
+    tube_radius: f32,
+    rings: usize,
+    sides: usize,
+) -> Result<Mesh, DemoError> {
+    let point = |ring: usize, side: usize| {
+        let (around, across) = (
+            TAU * ring as f32 / rings as f32,
+            TAU * side as f32 / sides as f32,
+        );
+        let distance = radius + tube_radius * across.cos();
+        vertex(
+            distance * around.cos(),
+            tube_radius * across.sin(),
+            distance * around.sin(),
+        )
+    };
+
+    let mut vertices = Vec::new();
+    for ring in 0..rings {
+        for side in 0..sides {
+            quad(
+                &mut vertices,
+                [
+                    &point(ring, side),
+                    &point(ring, side + 1),
+                    &point(ring + 1, side + 1),
+                    &point(ring + 1, side),
+                ],
+            );
+        }
+    }
+
+    Mesh::from_vertices(vertices)
+}
+
+// Upright along the y axis and centered on the origin, with both ends closed
+pub(crate) fn cylinder(radius: f32, height: f32, slices: usize) -> Result<Mesh, DemoError> {
+    let (top, bottom) = (-height / 2.0, height / 2.0);
+    let point = |slice: usize, y: f32| {
+        let angle = TAU * slice as f32 / slices as f32;
+        vertex(radius * angle.cos(), y, radius * angle.sin())
+    };
+
+    let mut vertices = Vec::new();
+    for slice in 0..slices {
+        quad(
+            &mut vertices,
+            [
+                &point(slice, top),
+                &point(slice, bottom),
+                &point(slice + 1, bottom),
+                &point(slice + 1, top),
+            ],
+        );
+        vertices.extend([
+            vertex(0.0, top, 0.0),
+            point(slice, top),
+            point(slice + 1, top),
+        ]);
+        vertices.extend([
+            vertex(0.0, bottom, 0.0),
+            point(slice + 1, bottom),
+            point(slice, bottom),
+        ]);
+    }
+
+    Mesh::from_vertices(vertices)
+}
+
+// The tip points up the screen, the base is closed
+pub(crate) fn cone(radius: f32, height: f32, slices: usize) -> Result<Mesh, DemoError> {
+    let (tip, base) = (-height / 2.0, height / 2.0);
+    let point = |slice: usize| {
+        let angle = TAU * slice as f32 / slices as f32;
+        vertex(radius * angle.cos(), base, radius * angle.sin())
+    };
+
+    let mut vertices = Vec::new();
+    for slice in 0..slices {
+        vertices.extend([vertex(0.0, tip, 0.0), point(slice), point(slice + 1)]);
+        vertices.extend([vertex(0.0, base, 0.0), point(slice + 1), point(slice)]);
+    }
+
+    Mesh::from_vertices(vertices)
+}
+
+// A flat square grid in the xz plane facing up the screen
+pub(crate) fn plane(size: f32, divisions: usize) -> Result<Mesh, DemoError> {
+    let cell = size / divisions as f32;
+    let point = |column: usize, row: usize| {
+        vertex(
+            column as f32 * cell - size / 2.0,
+            0.0,
+            row as f32 * cell - size / 2.0,
+        )
+    };
+
+    let mut vertices = Vec::new();
+    for row in 0..divisions {
+        for column in 0..divisions {
+            quad(
+                &mut vertices,
+                [
+                    &point(column, row),
+                    &point(column + 1, row),
+                    &point(column + 1, row + 1),
+                    &point(column, row + 1),
+                ],
+            );
+        }
+    }
+
+    Mesh::from_vertices(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+    }
+
+    fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+
+    fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    }
+
+    #[test]
+    fn every_shape_faces_outwards_and_fits_the_cube() {
+        let shapes = [
+            (Shape::Cube, 12),
+            (Shape::Sphere, 2 * SLICES + 2 * SLICES * (STACKS - 2)),
+            (Shape::Icosphere(0), 20),
+            (Shape::Icosphere(1), 80),
+            (
+                Shape::Icosphere(MAX_ICOSPHERE_LEVEL),
+                20 * 4_usize.pow(MAX_ICOSPHERE_LEVEL),
+            ),
+            (Shape::Torus, 2 * SLICES * SLICES / 2),
+            (Shape::Cylinder, 4 * SLICES),
+            (Shape::Cone, 2 * SLICES),
+            (Shape::Plane, 2 * PLANE_DIVISIONS * PLANE_DIVISIONS),
+        ];
+
+        for (shape, triangle_count) in shapes {
+            let mesh = shape.mesh().unwrap();
+            assert_eq!(mesh.triangles.len(), triangle_count, "{shape:?}");
+
+            let (mut extent, mut reach): (f32, f32) = (0.0, 0.0);
+            for triangle in &mesh.triangles {
+                let [a, b, c] = triangle
+                    .vertices
+                    .clone()
+                    .map(|vertex| [vertex.position.x, vertex.position.y, vertex.position.z]);
+                let normal = cross(sub(b, a), sub(c, a));
+                let centroid = [0, 1, 2].map(|i| (a[i] + b[i] + c[i]) / 3.0);
+
+                // Outwards is away from the middle of the shape, or of the tube of the torus
+                let inside = match shape {
+                    Shape::Torus => {
+                        let distance = centroid[0].hypot(centroid[2]);
+                        let ring = (1.0 - TORUS_TUBE) / distance;
+                        [centroid[0] * ring, 0.0, centroid[2] * ring]
+                    }
+                    // The plane has no inside, it faces up the screen
+                    Shape::Plane => [centroid[0], 1.0, centroid[2]],
+                    _ => [0.0; 3],
+                };
+                assert!(
+                    dot(normal, sub(centroid, inside)) > 0.0,
+                    "{shape:?} triangle {a:?} {b:?} {c:?} faces inwards"
+                );
+
+                for corner in [a, b, c] {
+                    extent = corner.iter().fold(extent, |extent, x| extent.max(x.abs()));
+                    reach = reach.max(dot(corner, corner).sqrt());
+                }
+            }
+
+            // Inside the cube from -1 to 1, but not smaller than the sphere within it
+            assert!(extent <= 1.0 + 1e-5, "{shape:?} reaches out to {extent}");
+            assert!(reach >= 1.0 - 1e-5, "{shape:?} only reaches {reach}");
+        }
+    }
+}
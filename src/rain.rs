@@ -0,0 +1,130 @@
+use crate::clock::{Clock, FrameLimiter};
+use crate::colors;
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::input;
+use crate::overlay;
+use crate::palette::put_glyph;
+use crate::rng::Rng;
+use crate::terminal;
+
+// How long the rain effect runs in seconds
+const RAIN_DURATION: f32 = 15.0;
+
+// Characters the streams are made of
+const RAIN_GLYPHS: &str = "0123456789ABCDEFZ:.=*+-<>|ｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄ";
+
+// Stream speeds in cells per second and lengths in cells
+const MIN_SPEED: f32 = 6.0;
+const MAX_SPEED: f32 = 24.0;
+const MIN_LENGTH: f32 = 4.0;
+const MAX_LENGTH: f32 = 20.0;
+
+// Chance per second of a changing glyph inside a tail
+const FLICKER_RATE: f32 = 3.0;
+
+// One falling stream per column, restarting above the screen once its tail left it
+struct Stream {
+    head: f32,
+    speed: f32,
+    length: f32,
+    glyphs: Vec<char>,
+}
+
+impl Stream {
+    fn new(rng: &mut Rng, glyphs: &[char], height: usize, start_above: bool) -> Stream {
+        let length = rng.range(MIN_LENGTH, MAX_LENGTH);
+
+        Stream {
+            // Streams created at the start are spread over the screen, later ones fall in from the top
+            head: match start_above {
+                true => -rng.range(0.0, height as f32),
+                false => rng.range(0.0, height as f32 + length),
+            },
+            speed: rng.range(MIN_SPEED, MAX_SPEED),
+            length,
+            glyphs: (0..height)
+                .map(|_| glyphs[rng.below(glyphs.len())])
+                .collect(),
+        }
+    }
+}
+
+// Sets up one stream per column, called again when the terminal is resized
+fn create_streams(rng: &mut Rng, glyphs: &[char], width: usize, height: usize) -> Vec<Stream> {
+    (0..width)
+        .map(|_| Stream::new(rng, glyphs, height, false))
+        .collect()
+}
+
+pub fn run_rain_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut rng = Rng::new(config.seed);
+
+    let glyphs: Vec<char> = RAIN_GLYPHS.chars().collect();
+    let (mut width, mut height) = (0, 0);
+    let mut streams: Vec<Stream> = Vec::new();
+
+    while clock.elapsed() < RAIN_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        if (screen_width as usize, screen_height as usize) != (width, height) {
+            (width, height) = (screen_width as usize, screen_height as usize);
+            streams = create_streams(&mut rng, &glyphs, width, height);
+            window.erase();
+        }
+
+        for (x, stream) in streams.iter().enumerate() {
+            let head = stream.head.floor() as i32;
+
+            for y in 0..height as i32 {
+                let distance = head - y;
+
+                if distance < 0 || distance as f32 >= stream.length {
+                    window.mvaddch(y, x as i32, ' ');
+                    continue;
+                }
+
+                // The head is white, the tail fades from bright to dim green
+                let fade = distance as f32 / stream.length;
+                let attributes = match distance {
+                    0 => colors::fg(colors::WHITE) | pancurses::A_BOLD,
+                    _ if fade < 0.3 => colors::fg(colors::GREEN) | pancurses::A_BOLD,
+                    _ if fade < 0.7 => colors::fg(colors::GREEN),
+                    _ => colors::fg(colors::GREEN) | pancurses::A_DIM,
+                };
+
+                window.attrset(attributes);
+                put_glyph(window, y, x as i32, stream.glyphs[y as usize]);
+            }
+        }
+        window.attrset(pancurses::A_NORMAL);
+
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+
+        let delta = clock.tick();
+        for stream in streams.iter_mut() {
+            stream.head += stream.speed * config.rain_speed * delta;
+
+            if stream.head - stream.length > height as f32 {
+                *stream = Stream::new(&mut rng, &glyphs, height, true);
+            }
+
+            // Let a few glyphs of each stream change while it falls
+            if rng.next_f32() < FLICKER_RATE * delta && height > 0 {
+                stream.glyphs[rng.below(height)] = glyphs[rng.below(glyphs.len())];
+            }
+        }
+    }
+
+    Ok(())
+}
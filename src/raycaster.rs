@@ -0,0 +1,343 @@
+use std::path::Path;
+
+use pancurses::{chtype, Input};
+
+use crate::clock::{Clock, FrameLimiter};
+use crate::colors;
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::field::Field;
+use crate::input;
+use crate::overlay;
+use crate::terminal;
+use crate::CELL_ASPECT;
+
+// How long the raycaster runs in seconds
+const RAYCASTER_DURATION: f32 = 20.0;
+
+// Horizontal field of view in degrees
+const FOV: f32 = 66.0;
+
+// Distance covered and angle turned per key press
+const MOVE_STEP: f32 = 0.2;
+const TURN_STEP: f32 = 0.08;
+
+// Until the first key press the camera looks around on its own, in radians per second
+const IDLE_TURN_SPEED: f32 = 0.3;
+
+// Closest distance the player can get to a wall
+const PLAYER_RADIUS: f32 = 0.2;
+
+// Wall characters from near to far, and the distance the last one is reached at
+const WALL_RAMP: [char; 8] = ['@', '#', '%', '=', '+', '-', ':', '.'];
+const VIEW_DISTANCE: f32 = 16.0;
+
+// Floor and ceiling characters from the horizon to the edge of the screen
+const FLOOR_RAMP: [char; 5] = [' ', '.', '-', '=', '#'];
+const CEILING_RAMP: [char; 4] = [' ', '`', '\'', '"'];
+
+// Wall colors picked by the digits 1 to 6 in a map, every other wall is white
+const WALL_COLORS: [i16; 6] = [
+    colors::RED,
+    colors::GREEN,
+    colors::YELLOW,
+    colors::BLUE,
+    colors::MAGENTA,
+    colors::CYAN,
+];
+
+// The map used unless another one is given, '@' marks the start facing east
+const DEFAULT_MAP: &str = "\
+################
+#@     #       #
+#      #   2   #
+#  11  #       #
+#  11      2   #
+#              #
+######  ########
+#    #  #      #
+#    3  #  44  #
+#       #  44  #
+#    3         #
+################";
+
+/*
+A grid of walls read from a text file. Spaces and '.' are floor, '@' is where
+the player starts and every other character is a wall. Everything outside of
+the map counts as wall too.
+*/
+#[derive(Clone)]
+pub struct Map {
+    cells: Field<char>,
+    start: (usize, usize),
+}
+
+impl Map {
+    pub fn parse(source: &str) -> Result<Map, DemoError> {
+        let lines: Vec<Vec<char>> = source
+            .lines()
+            .map(|line| line.trim_end_matches('\r').chars().collect())
+            .collect();
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+
+        let mut cells = Field::new(width, lines.len());
+        let mut start = None;
+
+        for (y, line) in lines.iter().enumerate() {
+            for x in 0..width {
+                let cell = match line.get(x).copied().unwrap_or(' ') {
+                    '@' => {
+                        start = Some((x, y));
+                        ' '
+                    }
+                    '.' => ' ',
+                    cell => cell,
+                };
+                cells.set(x, y, cell);
+            }
+        }
+
+        let start =
+            start.ok_or_else(|| DemoError::Config("map has no start position '@'".to_string()))?;
+
+        Ok(Map { cells, start })
+    }
+
+    pub fn load(path: &Path) -> Result<Map, DemoError> {
+        let source = std::fs::read_to_string(path).map_err(|error| DemoError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        Map::parse(&source)
+            .map_err(|error| DemoError::Config(format!("map {}: {error}", path.display())))
+    }
+
+    // The wall character at a cell, None for floor
+    fn wall(&self, x: i32, y: i32) -> Option<char> {
+        if x < 0 || y < 0 || x as usize >= self.cells.width() || y as usize >= self.cells.height() {
+            return Some('#');
+        }
+
+        match *self.cells.get(x as usize, y as usize) {
+            ' ' => None,
+            cell => Some(cell),
+        }
+    }
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Map::parse(DEFAULT_MAP).expect("the built-in map is valid")
+    }
+}
+
+struct Player {
+    x: f32,
+    y: f32,
+    angle: f32,
+}
+
+impl Player {
+    // Moves along the view direction, each axis on its own so the player slides along walls
+    fn walk(&mut self, map: &Map, distance: f32) {
+        let (dx, dy) = (self.angle.cos() * distance, self.angle.sin() * distance);
+
+        let margin = PLAYER_RADIUS * dx.signum();
+        if map
+            .wall((self.x + dx + margin).floor() as i32, self.y.floor() as i32)
+            .is_none()
+        {
+            self.x += dx;
+        }
+
+        let margin = PLAYER_RADIUS * dy.signum();
+        if map
+            .wall(self.x.floor() as i32, (self.y + dy + margin).floor() as i32)
+            .is_none()
+        {
+            self.y += dy;
+        }
+    }
+}
+
+// Where a ray hit a wall
+struct Hit {
+    distance: f32,
+    wall: char,
+    // Walls facing north or south are drawn darker, which makes corners readable
+    north_south: bool,
+}
+
+/*
+Steps the ray from grid line to grid line (DDA) until it enters a wall cell.
+The direction is the view direction plus a part of the camera plane, so the
+ray length in multiples of it is the distance perpendicular to the camera
+plane. Measuring to the player instead would make walls bulge in the middle.
+*/
+fn cast_ray(map: &Map, x: f32, y: f32, direction: (f32, f32)) -> Hit {
+    let (mut cell_x, mut cell_y) = (x.floor() as i32, y.floor() as i32);
+
+    // Ray length between two vertical and two horizontal grid lines
+    let delta_x = (1.0 / direction.0).abs();
+    let delta_y = (1.0 / direction.1).abs();
+
+    let (step_x, mut side_x) = match direction.0 < 0.0 {
+        true => (-1, (x - cell_x as f32) * delta_x),
+        false => (1, (cell_x as f32 + 1.0 - x) * delta_x),
+    };
+    let (step_y, mut side_y) = match direction.1 < 0.0 {
+        true => (-1, (y - cell_y as f32) * delta_y),
+        false => (1, (cell_y as f32 + 1.0 - y) * delta_y),
+    };
+
+    loop {
+        let north_south = side_x >= side_y;
+        let distance = match north_south {
+            false => {
+                cell_x += step_x;
+                side_x += delta_x;
+                side_x - delta_x
+            }
+            true => {
+                cell_y += step_y;
+                side_y += delta_y;
+                side_y - delta_y
+            }
+        };
+
+        if let Some(wall) = map.wall(cell_x, cell_y) {
+            return Hit {
+                distance: distance.max(0.0001),
+                wall,
+                north_south,
+            };
+        }
+    }
+}
+
+fn wall_shade(hit: &Hit) -> (char, chtype) {
+    let mut shade =
+        ((hit.distance / VIEW_DISTANCE * WALL_RAMP.len() as f32) as usize).min(WALL_RAMP.len() - 1);
+    if hit.north_south {
+        shade = (shade + 1).min(WALL_RAMP.len() - 1);
+    }
+
+    let color = match hit.wall.to_digit(10) {
+        Some(digit @ 1..=6) => WALL_COLORS[digit as usize - 1],
+        _ => colors::WHITE,
+    };
+    let brightness = match shade {
+        0..=1 => pancurses::A_BOLD,
+        2..=4 => pancurses::A_NORMAL,
+        _ => pancurses::A_DIM,
+    };
+
+    (WALL_RAMP[shade], colors::fg(color) | brightness)
+}
+
+// Characters of the floor and the ceiling get denser towards the screen edges
+fn backdrop(y: i32, height: i32) -> (char, chtype) {
+    let horizon = height / 2;
+    let depth = (y - horizon).abs() as f32 / (height - horizon).max(1) as f32;
+
+    match y < horizon {
+        true => {
+            let shade = ((depth * CEILING_RAMP.len() as f32) as usize).min(CEILING_RAMP.len() - 1);
+            (CEILING_RAMP[shade], colors::fg(colors::BLUE))
+        }
+        false => {
+            let shade = ((depth * FLOOR_RAMP.len() as f32) as usize).min(FLOOR_RAMP.len() - 1);
+            (
+                FLOOR_RAMP[shade],
+                colors::fg(colors::YELLOW) | pancurses::A_DIM,
+            )
+        }
+    }
+}
+
+pub fn run_raycaster_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+
+    let default_map;
+    let map = match &config.map {
+        Some(map) => map,
+        None => {
+            default_map = Map::default();
+            &default_map
+        }
+    };
+
+    let mut player = Player {
+        x: map.start.0 as f32 + 0.5,
+        y: map.start.1 as f32 + 0.5,
+        angle: 0.0,
+    };
+    let mut idle = true;
+
+    let plane_length = f32::tan(FOV.to_radians() / 2.0);
+
+    while clock.elapsed() < RAYCASTER_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        // Distance of the screen from the eye in columns, rows are twice as high
+        let projection = screen_width / 2.0 / plane_length;
+        let view = (player.angle.cos(), player.angle.sin());
+        let horizon = screen_height / 2.0;
+
+        for x in 0..screen_width as i32 {
+            // -1 at the left edge of the screen, 1 at the right edge
+            let camera_x = 2.0 * x as f32 / screen_width - 1.0;
+            let direction = (
+                view.0 - view.1 * plane_length * camera_x,
+                view.1 + view.0 * plane_length * camera_x,
+            );
+
+            let hit = cast_ray(map, player.x, player.y, direction);
+            let wall_height = projection / hit.distance / CELL_ASPECT;
+            let top = (horizon - wall_height / 2.0).round() as i32;
+            let bottom = (horizon + wall_height / 2.0).round() as i32;
+            let wall = wall_shade(&hit);
+
+            for y in 0..screen_height as i32 {
+                let (glyph, attributes) = match y >= top && y < bottom {
+                    true => wall,
+                    false => backdrop(y, screen_height as i32),
+                };
+
+                window.attrset(attributes);
+                window.mvaddch(y, x, glyph);
+            }
+        }
+        window.attrset(pancurses::A_NORMAL);
+
+        window.mvaddstr(0, 0, "[WASD] walk and turn");
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+
+        while let Some(key) = input::poll(window) {
+            match key {
+                Input::Character('w') | Input::KeyUp => player.walk(map, MOVE_STEP),
+                Input::Character('s') | Input::KeyDown => player.walk(map, -MOVE_STEP),
+                Input::Character('a') | Input::KeyLeft => player.angle -= TURN_STEP,
+                Input::Character('d') | Input::KeyRight => player.angle += TURN_STEP,
+                _ => continue,
+            }
+            idle = false;
+        }
+
+        let delta = clock.tick();
+        if idle {
+            player.angle += IDLE_TURN_SPEED * delta;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+// Small xorshift64* generator, good enough for picking effect parameters
+// without pulling in a dependency. The same seed always yields the same numbers.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on a zero state, so mix the seed first
+        Rng {
+            state: (seed ^ 0x9E37_79B9_7F4A_7C15).max(1),
+        }
+    }
+
+    // Seed derived from the current time for runs that don't need to be reproducible
+    pub fn time_seed() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniformly distributed in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Uniformly distributed in [min, max)
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    // Uniformly distributed in [0, n)
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+}
@@ -0,0 +1,86 @@
+use crate::clock::{Clock, FrameLimiter};
+use crate::config::Config;
+use crate::cube3d::Vec3;
+use crate::error::DemoError;
+use crate::input;
+use crate::overlay;
+use crate::palette::put_glyph;
+use crate::terminal;
+use crate::texture::Texture;
+use crate::CELL_ASPECT;
+
+// How long the rotozoom effect runs in seconds
+const ROTOZOOM_DURATION: f32 = 10.0;
+
+// Rotation in radians per second, and how fast and far the zoom swings
+const ROTATION_SPEED: f32 = 0.5;
+const ZOOM_SPEED: f32 = 0.7;
+const ZOOM_RANGE: f32 = 0.7;
+
+pub fn run_rotozoom_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+
+    let texture = config.texture.clone().unwrap_or_else(Texture::procedural);
+
+    while clock.elapsed() < ROTOZOOM_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        let t = clock.elapsed();
+        let zoom = 1.0 + ZOOM_RANGE * f32::sin(t * ZOOM_SPEED);
+
+        /*
+        Instead of rotating every cell, only the texture steps of one cell to
+        the right and one cell down are rotated (with the cube's z rotation)
+        and then added up while walking over the screen.
+        */
+        let step_x = Vec3 {
+            x: zoom,
+            y: 0.0,
+            z: 0.0,
+        }
+        .rotate_z(t * ROTATION_SPEED);
+        let step_y = Vec3 {
+            x: 0.0,
+            y: zoom * CELL_ASPECT,
+            z: 0.0,
+        }
+        .rotate_z(t * ROTATION_SPEED);
+
+        // Start at the top left corner so the rotation happens around the screen center
+        let center_x = screen_width / 2.0;
+        let center_y = screen_height / 2.0;
+        let origin_u = -center_x * step_x.x - center_y * step_y.x;
+        let origin_v = -center_x * step_x.y - center_y * step_y.y;
+
+        for y in 0..screen_height as i32 {
+            let mut u = origin_u + y as f32 * step_y.x;
+            let mut v = origin_v + y as f32 * step_y.y;
+
+            for x in 0..screen_width as i32 {
+                put_glyph(
+                    window,
+                    y,
+                    x,
+                    texture.sample(u.floor() as i32, v.floor() as i32),
+                );
+
+                u += step_x.x;
+                v += step_x.y;
+            }
+        }
+
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+        clock.tick();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use pancurses::Input;
+
+use crate::clock::{Clock, FrameLimiter};
+use crate::config::Config;
+use crate::cube3d::{Vec3, Vertex};
+use crate::error::DemoError;
+use crate::input;
+use crate::overlay;
+use crate::rng::Rng;
+
+// How long the starfield effect runs in seconds
+const STARFIELD_DURATION: f32 = 15.0;
+
+const STAR_COUNT: usize = 400;
+
+// Stars are spawned in a box in front of the camera
+const SPREAD: f32 = 10.0;
+const NEAR_Z: f32 = 0.5;
+const FAR_Z: f32 = 20.0;
+
+// Speed changes per key press and the allowed range, in units per second
+const SPEED_STEP: f32 = 1.0;
+const MIN_SPEED: f32 = 0.0;
+const MAX_SPEED: f32 = 40.0;
+
+fn spawn_star(rng: &mut Rng, z: f32) -> Vec3 {
+    Vec3 {
+        x: rng.range(-SPREAD, SPREAD),
+        y: rng.range(-SPREAD, SPREAD),
+        z,
+    }
+}
+
+// Closer stars are drawn brighter and bigger
+fn depth_glyph(z: f32) -> (char, pancurses::chtype) {
+    match z / FAR_Z {
+        depth if depth < 0.25 => ('*', pancurses::A_BOLD),
+        depth if depth < 0.6 => ('+', pancurses::A_NORMAL),
+        _ => ('.', pancurses::A_DIM),
+    }
+}
+
+pub fn run_starfield_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut rng = Rng::new(config.seed);
+
+    let mut speed = config.star_speed;
+    let mut stars: Vec<Vec3> = (0..STAR_COUNT)
+        .map(|_| {
+            let z = rng.range(NEAR_Z, FAR_Z);
+            spawn_star(&mut rng, z)
+        })
+        .collect();
+
+    while clock.elapsed() < STARFIELD_DURATION && !input::quit_requested() {
+        window.erase();
+
+        for star in &stars {
+            // Same perspective projection and screen mapping as the cube
+            let projected = star.project();
+            if projected.x.abs() > 1.0 || projected.y.abs() > 1.0 {
+                continue;
+            }
+
+            let screen = Vertex {
+                position: projected,
+            }
+            .scale_into_screen_2d()
+            .round();
+            let (glyph, attributes) = depth_glyph(star.z);
+
+            window.attrset(attributes);
+            window.mvaddch(screen.y as i32, screen.x as i32, glyph);
+        }
+        window.attrset(pancurses::A_NORMAL);
+
+        window.mvaddstr(0, 0, format!("speed {speed:.0}  [+/-]"));
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+
+        while let Some(key) = input::poll(window) {
+            match key {
+                Input::Character('+') | Input::KeyUp => speed += SPEED_STEP,
+                Input::Character('-') | Input::KeyDown => speed -= SPEED_STEP,
+                _ => {}
+            }
+            speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        }
+
+        let delta = clock.tick();
+        for star in stars.iter_mut() {
+            star.z -= speed * delta;
+
+            // Stars passing the camera start over in the distance
+            if star.z < NEAR_Z {
+                *star = spawn_star(&mut rng, FAR_Z);
+            }
+        }
+    }
+
+    Ok(())
+}
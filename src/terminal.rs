@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set from the SIGTSTP handler, the actual suspending happens outside of it
+static SUSPEND_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// Set from the SIGCONT handler so the screen gets redrawn after a resume
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+/*
+Sets up the terminal for the demo. The panic hook and the signal handlers are
+installed before curses starts so that the terminal is restored no matter how
+the demo ends, and so ncurses keeps our handlers instead of installing its own.
+*/
+pub fn init() -> pancurses::Window {
+    install_panic_hook();
+    install_signal_handlers();
+
+    let window = pancurses::initscr();
+    pancurses::noecho();
+    window.keypad(true);
+    window.nodelay(true);
+    crate::colors::init();
+
+    window
+}
+
+/*
+Convert maximum screen height & width to f32
+to be used later in the calculations of the effects. Called again whenever the
+terminal gets resized.
+*/
+pub fn update_screen_size(window: &pancurses::Window) {
+    unsafe {
+        (crate::SCREEN_HEIGHT, crate::SCREEN_WIDTH) = {
+            let (y, x) = window.get_max_yx();
+            (y as f32, x as f32)
+        };
+    }
+}
+
+// The size of the terminal in cells as of the last update_screen_size
+pub fn screen_size() -> (f32, f32) {
+    unsafe { (crate::SCREEN_WIDTH, crate::SCREEN_HEIGHT) }
+}
+
+pub fn restore() {
+    pancurses::endwin();
+}
+
+// Leaves curses mode before the panic message is printed, otherwise it would
+// end up garbled on the screen and the shell would be left unusable
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+}
+
+// Handles signals that were caught since the last frame, called while polling input
+pub fn service_signals(window: &pancurses::Window) {
+    if SUSPEND_REQUESTED.swap(false, Ordering::Relaxed) {
+        restore();
+        suspend();
+        RESUMED.store(true, Ordering::Relaxed);
+    }
+
+    if RESUMED.swap(false, Ordering::Relaxed) {
+        // Refreshing after endwin puts the terminal back into curses mode
+        window.clearok(true);
+        window.refresh();
+    }
+}
+
+#[cfg(unix)]
+fn install_signal_handlers() {
+    unsafe {
+        set_handler(
+            libc::SIGINT,
+            handle_quit as extern "C" fn(libc::c_int) as usize,
+        );
+        set_handler(
+            libc::SIGTERM,
+            handle_quit as extern "C" fn(libc::c_int) as usize,
+        );
+        set_handler(
+            libc::SIGTSTP,
+            handle_suspend as extern "C" fn(libc::c_int) as usize,
+        );
+        set_handler(
+            libc::SIGCONT,
+            handle_resume as extern "C" fn(libc::c_int) as usize,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn install_signal_handlers() {}
+
+// Stops the process with the default SIGTSTP action and returns once it is continued
+#[cfg(unix)]
+fn suspend() {
+    unsafe {
+        set_handler(libc::SIGTSTP, libc::SIG_DFL);
+        libc::raise(libc::SIGTSTP);
+        set_handler(
+            libc::SIGTSTP,
+            handle_suspend as extern "C" fn(libc::c_int) as usize,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn suspend() {}
+
+#[cfg(unix)]
+unsafe fn set_handler(signal: libc::c_int, handler: libc::sighandler_t) {
+    let mut action: libc::sigaction = std::mem::zeroed();
+    action.sa_sigaction = handler;
+    libc::sigemptyset(&mut action.sa_mask);
+    libc::sigaction(signal, &action, std::ptr::null_mut());
+}
+
+// Signal handlers only set flags, everything else happens in service_signals
+#[cfg(unix)]
+extern "C" fn handle_quit(_signal: libc::c_int) {
+    crate::input::request_quit();
+}
+
+#[cfg(unix)]
+extern "C" fn handle_suspend(_signal: libc::c_int) {
+    SUSPEND_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+extern "C" fn handle_resume(_signal: libc::c_int) {
+    RESUMED.store(true, Ordering::Relaxed);
+}
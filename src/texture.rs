@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use crate::error::DemoError;
+use crate::field::Field;
+
+// Size and characters of the built-in texture
+const PROCEDURAL_SIZE: usize = 32;
+const PROCEDURAL_RAMP: [char; 8] = [' ', '.', ':', '-', '=', '+', '#', '@'];
+
+// A character image that repeats in both directions, sampled by the texture effects
+#[derive(Clone)]
+pub struct Texture {
+    glyphs: Field<char>,
+}
+
+impl Texture {
+    // The classic XOR pattern, used when no texture file is given
+    pub fn procedural() -> Texture {
+        let mut glyphs = Field::new(PROCEDURAL_SIZE, PROCEDURAL_SIZE);
+
+        for y in 0..PROCEDURAL_SIZE {
+            for x in 0..PROCEDURAL_SIZE {
+                let shade = (x ^ y) * PROCEDURAL_RAMP.len() / PROCEDURAL_SIZE;
+                glyphs.set(x, y, PROCEDURAL_RAMP[shade]);
+            }
+        }
+
+        Texture { glyphs }
+    }
+
+    // Reads a plain text file, lines shorter than the longest one are padded with spaces
+    pub fn load(path: &Path) -> Result<Texture, DemoError> {
+        let source = std::fs::read_to_string(path).map_err(|error| DemoError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let lines: Vec<Vec<char>> = source
+            .lines()
+            .map(|line| line.trim_end_matches('\r').chars().collect())
+            .collect();
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+
+        if width == 0 {
+            return Err(DemoError::Config(format!(
+                "texture {} is empty",
+                path.display()
+            )));
+        }
+
+        let mut glyphs = Field::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            for x in 0..width {
+                glyphs.set(x, y, line.get(x).copied().unwrap_or(' '));
+            }
+        }
+
+        Ok(Texture { glyphs })
+    }
+
+    pub fn width(&self) -> usize {
+        self.glyphs.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.glyphs.height()
+    }
+
+    // Coordinates outside of the texture wrap around
+    pub fn sample(&self, u: i32, v: i32) -> char {
+        *self.glyphs.get(
+            u.rem_euclid(self.width() as i32) as usize,
+            v.rem_euclid(self.height() as i32) as usize,
+        )
+    }
+}
@@ -0,0 +1,117 @@
+use crate::clock::{Clock, FrameLimiter};
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::field::Field;
+use crate::input;
+use crate::overlay;
+use crate::palette::put_glyph;
+use crate::terminal;
+use crate::texture::Texture;
+use crate::CELL_ASPECT;
+
+// How long the tunnel effect runs in seconds
+const TUNNEL_DURATION: f32 = 10.0;
+
+// How deep the texture is stretched into the tunnel, larger is longer
+const DEPTH_RATIO: f32 = 32.0;
+
+// Texture repetitions per second along and around the tunnel
+const FORWARD_SPEED: f32 = 1.0;
+const ROTATION_SPEED: f32 = 0.1;
+
+/*
+Per cell texture coordinates, only depending on the cell position and rebuilt
+when the terminal is resized: the distance from the center becomes the depth
+into the tunnel, the angle around the center the position around its wall.
+*/
+struct TunnelTables {
+    depth: Field<f32>,
+    angle: Field<f32>,
+}
+
+impl TunnelTables {
+    fn new() -> TunnelTables {
+        TunnelTables {
+            depth: Field::new(0, 0),
+            angle: Field::new(0, 0),
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize, texture: &Texture) {
+        if self.depth.width() == width && self.depth.height() == height {
+            return;
+        }
+
+        self.depth.resize(width, height);
+        self.angle.resize(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let dx = x as f32 - width as f32 / 2.0;
+                let dy = (y as f32 - height as f32 / 2.0) * CELL_ASPECT;
+                let distance = f32::sqrt(dx * dx + dy * dy).max(1.0);
+
+                self.depth
+                    .set(x, y, DEPTH_RATIO * texture.height() as f32 / distance);
+                self.angle.set(
+                    x,
+                    y,
+                    texture.width() as f32 * (f32::atan2(dy, dx) / std::f32::consts::TAU + 0.5),
+                );
+            }
+        }
+    }
+}
+
+pub fn run_tunnel_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+
+    let texture = config.texture.clone().unwrap_or_else(Texture::procedural);
+    let mut tables = TunnelTables::new();
+
+    while clock.elapsed() < TUNNEL_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        tables.resize(screen_width as usize, screen_height as usize, &texture);
+
+        let t = clock.elapsed();
+        let shift_depth = t * FORWARD_SPEED * texture.height() as f32;
+        let shift_angle = t * ROTATION_SPEED * texture.width() as f32;
+
+        // The far end of the tunnel fades out, the walls close by stand out
+        let far = 4.0 * texture.height() as f32;
+        let near = texture.height() as f32;
+
+        for y in 0..tables.depth.height() {
+            for x in 0..tables.depth.width() {
+                let depth = *tables.depth.get(x, y);
+                let glyph = texture.sample(
+                    (*tables.angle.get(x, y) + shift_angle) as i32,
+                    (depth + shift_depth) as i32,
+                );
+
+                window.attrset(match depth {
+                    depth if depth > far => pancurses::A_DIM,
+                    depth if depth < near => pancurses::A_BOLD,
+                    _ => pancurses::A_NORMAL,
+                });
+                put_glyph(window, y as i32, x as i32, glyph);
+            }
+        }
+        window.attrset(pancurses::A_NORMAL);
+
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+        clock.tick();
+    }
+
+    Ok(())
+}
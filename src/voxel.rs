@@ -0,0 +1,530 @@
+use std::path::Path;
+
+use pancurses::chtype;
+
+use crate::clock::{Clock, FrameLimiter};
+use crate::colors;
+use crate::config::Config;
+use crate::error::DemoError;
+use crate::field::Field;
+use crate::input;
+use crate::overlay;
+use crate::rng::Rng;
+use crate::terminal;
+use crate::CELL_ASPECT;
+
+// How long the voxel terrain runs in seconds
+const VOXEL_DURATION: f32 = 15.0;
+
+// Size of the generated height map, which repeats in both directions
+const MAP_SIZE: usize = 256;
+
+// Coarsest detail of the generated terrain in map cells, every octave halves it
+const NOISE_PERIOD: usize = 64;
+const NOISE_OCTAVES: u32 = 5;
+
+// Horizontal field of view in degrees
+const FOV: f32 = 70.0;
+
+// Height of the highest terrain and how far the camera stays above the ground, in map cells
+const HEIGHT_SCALE: f32 = 48.0;
+const CAMERA_ALTITUDE: f32 = 24.0;
+
+// Camera speed in map cells per second and how much it sways from side to side
+const CAMERA_SPEED: f32 = 20.0;
+const CAMERA_SWAY: f32 = 0.6;
+
+// The terrain is sampled in steps growing with the distance, less detail is visible far away
+const DRAW_DISTANCE: f32 = 300.0;
+const FIRST_STEP: f32 = 0.5;
+const STEP_GROWTH: f32 = 0.015;
+
+// Everything lower than this is covered with water
+const WATER_LEVEL: f32 = 0.3;
+
+// Generated colors: beaches up to the first height, bare rock above the second
+// where it is dry, snow above the third
+const BEACH_LEVEL: f32 = 0.35;
+const ROCK_LEVEL: f32 = 0.6;
+const SNOW_LEVEL: f32 = 0.8;
+
+// Moisture below which the generated lowlands are dry grass instead of green
+const DRY_MOISTURE: f32 = 0.4;
+
+// Largest width and height of a loaded height or color map
+const MAX_IMAGE_SIZE: usize = 4096;
+
+// Terrain characters from near to far
+const TERRAIN_RAMP: [char; 8] = ['#', '%', '&', '*', '+', '=', '-', '.'];
+
+/*
+Heights from 0 to 1, either generated from layered value noise or read from a
+grayscale PGM image. Both repeat in both directions.
+*/
+#[derive(Clone)]
+pub struct Heightmap {
+    heights: Field<f32>,
+}
+
+impl Heightmap {
+    pub fn procedural(seed: u64) -> Heightmap {
+        Heightmap {
+            heights: fractal_noise(&mut Rng::new(seed)),
+        }
+    }
+
+    // Reads a plain (P2) or binary (P5) PGM image
+    pub fn load(path: &Path) -> Result<Heightmap, DemoError> {
+        let image = read_image(path, "height map")?;
+        if image.channels != 1 {
+            return Err(DemoError::Config(format!(
+                "height map {}: not a P2 or P5 PGM image",
+                path.display()
+            )));
+        }
+
+        let mut heights = Field::new(image.width, image.height);
+        for (index, value) in image.values.iter().enumerate() {
+            heights.set(
+                index % image.width,
+                index / image.width,
+                *value as f32 / image.max_value as f32,
+            );
+        }
+
+        Ok(Heightmap { heights })
+    }
+
+    // Water is flat, so the returned height is never below the water level
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let x = (x.floor() as i32).rem_euclid(self.heights.width() as i32) as usize;
+        let y = (y.floor() as i32).rem_euclid(self.heights.height() as i32) as usize;
+
+        self.heights.get(x, y).max(WATER_LEVEL)
+    }
+}
+
+/*
+A curses color for every cell of the terrain, either generated from the
+heights and a second layer of noise standing for moisture, or read from a PPM
+image. A color map of another size than the height map is stretched over it.
+*/
+#[derive(Clone)]
+pub struct Colormap {
+    colors: Field<i16>,
+}
+
+impl Colormap {
+    pub fn procedural(map: &Heightmap, seed: u64) -> Colormap {
+        let moisture = fractal_noise(&mut Rng::new(seed.wrapping_add(1)));
+        let (width, height) = (map.heights.width(), map.heights.height());
+        let mut colors = Field::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let wet =
+                    *moisture.get(x % moisture.width(), y % moisture.height()) >= DRY_MOISTURE;
+                let color = match *map.heights.get(x, y) {
+                    terrain if terrain <= WATER_LEVEL => colors::BLUE,
+                    terrain if terrain < BEACH_LEVEL => colors::YELLOW,
+                    terrain if terrain < ROCK_LEVEL && wet => colors::GREEN,
+                    terrain if terrain < ROCK_LEVEL => colors::YELLOW,
+                    terrain if terrain < SNOW_LEVEL && !wet => colors::RED,
+                    _ => colors::WHITE,
+                };
+                colors.set(x, y, color);
+            }
+        }
+
+        Colormap { colors }
+    }
+
+    // Reads a plain (P3) or binary (P6) PPM image, every pixel becomes the closest curses color
+    pub fn load(path: &Path) -> Result<Colormap, DemoError> {
+        let image = read_image(path, "color map")?;
+        if image.channels != 3 {
+            return Err(DemoError::Config(format!(
+                "color map {}: not a P3 or P6 PPM image",
+                path.display()
+            )));
+        }
+
+        let mut colors = Field::new(image.width, image.height);
+        for (index, pixel) in image.values.chunks(3).enumerate() {
+            colors.set(
+                index % image.width,
+                index / image.width,
+                closest_color(pixel, image.max_value),
+            );
+        }
+
+        Ok(Colormap { colors })
+    }
+
+    fn sample(&self, map: &Heightmap, x: f32, y: f32) -> i16 {
+        let (width, height) = (self.colors.width() as f32, self.colors.height() as f32);
+        let x = x * width / map.heights.width() as f32;
+        let y = y * height / map.heights.height() as f32;
+
+        *self.colors.get(
+            (x.floor() as i32).rem_euclid(width as i32) as usize,
+            (y.floor() as i32).rem_euclid(height as i32) as usize,
+        )
+    }
+}
+
+/*
+Compares hues rather than brightness, as dark colors on the terminal background
+would hide the terrain: the pixel is scaled up until its brightest channel is
+full, black pixels become white.
+*/
+fn closest_color(pixel: &[usize], max_value: usize) -> i16 {
+    const COLORS: [(i16, [f32; 3]); 7] = [
+        (colors::RED, [1.0, 0.0, 0.0]),
+        (colors::GREEN, [0.0, 1.0, 0.0]),
+        (colors::YELLOW, [1.0, 1.0, 0.0]),
+        (colors::BLUE, [0.0, 0.0, 1.0]),
+        (colors::MAGENTA, [1.0, 0.0, 1.0]),
+        (colors::CYAN, [0.0, 1.0, 1.0]),
+        (colors::WHITE, [1.0, 1.0, 1.0]),
+    ];
+
+    let peak = pixel.iter().copied().max().unwrap_or(0);
+    if peak == 0 {
+        return colors::WHITE;
+    }
+    let channels: Vec<f32> = pixel
+        .iter()
+        .map(|&value| value.min(max_value) as f32 / peak as f32)
+        .collect();
+
+    let distance = |rgb: &[f32; 3]| -> f32 {
+        rgb.iter()
+            .zip(&channels)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum()
+    };
+
+    COLORS
+        .iter()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map_or(colors::WHITE, |&(color, _)| color)
+}
+
+// Layered value noise from 0 to 1 covering the repeating map
+fn fractal_noise(rng: &mut Rng) -> Field<f32> {
+    let mut noise = Field::new(MAP_SIZE, MAP_SIZE);
+
+    let mut period = NOISE_PERIOD;
+    let mut amplitude = 1.0;
+    for _ in 0..NOISE_OCTAVES {
+        let cells = MAP_SIZE / period;
+        let mut lattice = Field::new(cells, cells);
+        for y in 0..cells {
+            for x in 0..cells {
+                lattice.set(x, y, rng.next_f32());
+            }
+        }
+
+        for y in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                let value =
+                    smooth_sample(&lattice, x as f32 / period as f32, y as f32 / period as f32);
+                noise.set(x, y, noise.get(x, y) + value * amplitude);
+            }
+        }
+
+        period /= 2;
+        amplitude /= 2.0;
+    }
+
+    normalize(&mut noise);
+    noise
+}
+
+// Stretches the values to cover the whole range from 0 to 1
+fn normalize(field: &mut Field<f32>) {
+    let (width, height) = (field.width(), field.height());
+    let rows = (0..height).flat_map(|y| field.row(y).iter().copied());
+    let (low, high) = rows.fold((f32::MAX, f32::MIN), |(low, high), value| {
+        (low.min(value), high.max(value))
+    });
+    let range = (high - low).max(f32::EPSILON);
+
+    for y in 0..height {
+        for x in 0..width {
+            field.set(x, y, (field.get(x, y) - low) / range);
+        }
+    }
+}
+
+// Bilinear sample of a repeating lattice, smoothed so the cell borders don't show
+fn smooth_sample(lattice: &Field<f32>, x: f32, y: f32) -> f32 {
+    let (width, height) = (lattice.width(), lattice.height());
+    let (left, top) = (x.floor() as usize % width, y.floor() as usize % height);
+    let (right, bottom) = ((left + 1) % width, (top + 1) % height);
+
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (u, v) = (smooth(x.fract()), smooth(y.fract()));
+
+    let upper = lattice.get(left, top) * (1.0 - u) + lattice.get(right, top) * u;
+    let lower = lattice.get(left, bottom) * (1.0 - u) + lattice.get(right, bottom) * u;
+    upper * (1.0 - v) + lower * v
+}
+
+// Pixels of a PGM or PPM image, channels values per pixel and each at most max_value
+struct Image {
+    width: usize,
+    height: usize,
+    channels: usize,
+    max_value: usize,
+    values: Vec<usize>,
+}
+
+fn read_image(path: &Path, kind: &str) -> Result<Image, DemoError> {
+    let data = std::fs::read(path).map_err(|error| DemoError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    parse_netpbm(&data)
+        .map_err(|error| DemoError::Config(format!("{kind} {}: {error}", path.display())))
+}
+
+// Plain (P2, P3) and binary (P5, P6) grayscale and color images
+fn parse_netpbm(data: &[u8]) -> Result<Image, DemoError> {
+    let mut position = 0;
+
+    // Header fields are separated by whitespace and may be interrupted by '#' comments
+    let mut next_token = || -> Result<&[u8], DemoError> {
+        loop {
+            match data.get(position) {
+                Some(byte) if byte.is_ascii_whitespace() => position += 1,
+                Some(b'#') => {
+                    while data.get(position).is_some_and(|&byte| byte != b'\n') {
+                        position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(DemoError::Config("unexpected end of file".to_string())),
+            }
+        }
+
+        let start = position;
+        while data
+            .get(position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        Ok(&data[start..position])
+    };
+
+    let magic = next_token()?.to_vec();
+    let (channels, binary) = match magic.as_slice() {
+        b"P2" => (1, false),
+        b"P3" => (3, false),
+        b"P5" => (1, true),
+        b"P6" => (3, true),
+        _ => return Err(DemoError::Config("not a PGM or PPM image".to_string())),
+    };
+
+    let mut number = || -> Result<usize, DemoError> {
+        let token = next_token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| {
+                DemoError::Config(format!(
+                    "invalid number '{}'",
+                    String::from_utf8_lossy(token)
+                ))
+            })
+    };
+
+    let (width, height, max_value) = (number()?, number()?, number()?);
+    if width == 0 || height == 0 || max_value == 0 || max_value > u16::MAX as usize {
+        return Err(DemoError::Config(
+            "invalid image size or maximum value".to_string(),
+        ));
+    }
+    if width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+        return Err(DemoError::Config(format!(
+            "image is larger than {MAX_IMAGE_SIZE}x{MAX_IMAGE_SIZE}"
+        )));
+    }
+
+    let too_large = || DemoError::Config("image is too large".to_string());
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(too_large)?;
+    let values: Vec<usize> = match binary {
+        false => (0..count).map(|_| number()).collect::<Result<_, _>>()?,
+        true => {
+            // A single whitespace character separates the header from the pixels
+            let start = position + 1;
+            let bytes_per_value = if max_value < 256 { 1 } else { 2 };
+            let end = count
+                .checked_mul(bytes_per_value)
+                .and_then(|length| length.checked_add(start))
+                .ok_or_else(too_large)?;
+            let pixels = data
+                .get(start..end)
+                .ok_or_else(|| DemoError::Config("image data is truncated".to_string()))?;
+
+            pixels
+                .chunks(bytes_per_value)
+                .map(|value| value.iter().fold(0, |sum, &byte| sum << 8 | byte as usize))
+                .collect()
+        }
+    };
+
+    Ok(Image {
+        width,
+        height,
+        channels,
+        max_value,
+        values: values.iter().map(|&value| value.min(max_value)).collect(),
+    })
+}
+
+// Character and brightness of a terrain column by its height and distance, in the given color
+fn terrain_shade(height: f32, distance: f32, color: i16) -> (char, chtype) {
+    // Most of the visible terrain is close to the camera, the root spreads it over more characters
+    let shade = ((distance / DRAW_DISTANCE).sqrt() * TERRAIN_RAMP.len() as f32) as usize;
+    let shade = shade.min(TERRAIN_RAMP.len() - 1);
+
+    match height {
+        height if height <= WATER_LEVEL => ('~', colors::fg(color)),
+        height if height < ROCK_LEVEL => (TERRAIN_RAMP[shade], colors::fg(color)),
+        height if height < SNOW_LEVEL => {
+            (TERRAIN_RAMP[shade], colors::fg(color) | pancurses::A_DIM)
+        }
+        _ => (TERRAIN_RAMP[shade], colors::fg(color) | pancurses::A_BOLD),
+    }
+}
+
+/*
+Comanche style voxel space: for every column the terrain is sampled from the
+camera outwards, each sample covering the screen from its projected height up
+to the highest point drawn in that column so far (the y-buffer). Whatever lies
+behind a closer hill is never drawn.
+*/
+fn draw_terrain(
+    window: &pancurses::Window,
+    map: &Heightmap,
+    colors: &Colormap,
+    camera: (f32, f32, f32),
+    angle: f32,
+    width: usize,
+    height: usize,
+) {
+    let plane_length = f32::tan(FOV.to_radians() / 2.0);
+    let projection = width as f32 / 2.0 / plane_length / CELL_ASPECT;
+    let horizon = height as f32 / 3.0;
+
+    let (forward_x, forward_y) = (angle.cos(), angle.sin());
+    let (right_x, right_y) = (-forward_y, forward_x);
+
+    let mut y_buffer = vec![height as i32; width];
+    let mut distance = 1.0;
+    let mut step = FIRST_STEP;
+
+    while distance < DRAW_DISTANCE {
+        for (x, lowest) in y_buffer.iter_mut().enumerate() {
+            // -1 at the left edge of the screen, 1 at the right edge
+            let camera_x = 2.0 * x as f32 / width as f32 - 1.0;
+            let offset = camera_x * plane_length * distance;
+
+            let (map_x, map_y) = (
+                camera.0 + forward_x * distance + right_x * offset,
+                camera.1 + forward_y * distance + right_y * offset,
+            );
+            let terrain = map.sample(map_x, map_y);
+            let top = ((camera.2 - terrain * HEIGHT_SCALE) / distance * projection + horizon)
+                .max(0.0) as i32;
+
+            if top < *lowest {
+                let (glyph, attributes) =
+                    terrain_shade(terrain, distance, colors.sample(map, map_x, map_y));
+                window.attrset(attributes);
+                for y in top..*lowest {
+                    window.mvaddch(y, x as i32, glyph);
+                }
+                *lowest = top;
+            }
+        }
+
+        distance += step;
+        step += STEP_GROWTH;
+    }
+
+    window.attrset(pancurses::A_NORMAL);
+}
+
+pub fn run_voxel_demo(
+    window: &pancurses::Window,
+    clock: &mut Clock,
+    config: &Config,
+) -> Result<(), DemoError> {
+    clock.reset();
+    let mut limiter = FrameLimiter::new(config.fps);
+
+    let generated;
+    let map = match &config.heightmap {
+        Some(map) => map,
+        None => {
+            generated = Heightmap::procedural(config.seed);
+            &generated
+        }
+    };
+
+    let generated_colors;
+    let colors = match &config.colormap {
+        Some(colors) => colors,
+        None => {
+            generated_colors = Colormap::procedural(map, config.seed);
+            &generated_colors
+        }
+    };
+
+    let (mut camera_x, mut camera_y) = (0.0, 0.0);
+    let mut altitude = map.sample(camera_x, camera_y) * HEIGHT_SCALE + CAMERA_ALTITUDE;
+
+    while clock.elapsed() < VOXEL_DURATION && !input::quit_requested() {
+        let (screen_width, screen_height) = terminal::screen_size();
+
+        // The camera flies ahead on a winding path, the map repeats endlessly
+        let angle = CAMERA_SWAY * f32::sin(clock.elapsed() * 0.4);
+
+        window.erase();
+        draw_terrain(
+            window,
+            map,
+            colors,
+            (camera_x, camera_y, altitude),
+            angle,
+            screen_width as usize,
+            screen_height as usize,
+        );
+        overlay::draw_configured(window, config, clock.elapsed());
+        window.refresh();
+
+        limiter.wait();
+        input::discard(window);
+
+        let delta = clock.tick();
+        camera_x += angle.cos() * CAMERA_SPEED * delta;
+        camera_y += angle.sin() * CAMERA_SPEED * delta;
+
+        // Follow the ground a bit ahead of the camera smoothly, so it rises before hills
+        let ground = map
+            .sample(camera_x + angle.cos() * 10.0, camera_y + angle.sin() * 10.0)
+            .max(map.sample(camera_x, camera_y));
+        let target = ground * HEIGHT_SCALE + CAMERA_ALTITUDE;
+        altitude += (target - altitude) * (2.0 * delta).min(1.0);
+    }
+
+    Ok(())
+}